@@ -0,0 +1,91 @@
+//! Times day 4 match counting with the bitset cards against the old pair of
+//! `HashSet`s, on generated tables.
+//!
+//! usage: cargo run --release --example day4_bench -- [cards] [max-number]
+
+use adventofcode2023::day4::scan_cards;
+use std::collections::HashSet;
+use std::env;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let n_cards: u32 = args
+        .next()
+        .map_or(1_000_000, |n| n.parse().expect("invalid card count"));
+    let max_number: u32 = args
+        .next()
+        .map_or(99, |n| n.parse().expect("invalid max number"));
+
+    let input = generate(n_cards, max_number);
+    println!(
+        "{} cards, numbers up to {}, {} MB of input",
+        n_cards,
+        max_number,
+        input.len() / 1_000_000
+    );
+
+    let (bitset_matches, bitset_time) = time(|| {
+        let cards = scan_cards(&input).expect("generated input should parse");
+        cards.iter().map(|card| card.matches() as u64).sum()
+    });
+    let (hashset_matches, hashset_time) =
+        time(|| input.lines().map(|line| hashset_matches(line) as u64).sum());
+    assert_eq!(bitset_matches, hashset_matches);
+
+    println!("{} matches", bitset_matches);
+    println!("bitset:  {:?}", bitset_time);
+    println!("hashset: {:?}", hashset_time);
+}
+
+fn time<F: FnOnce() -> u64>(f: F) -> (u64, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Matches the way cards used to be read: both sides into a `HashSet`, then
+/// intersect.
+fn hashset_matches(line: &str) -> usize {
+    let (_, numbers) = line.split_once(':').unwrap();
+    let (winning, game) = numbers.split_once('|').unwrap();
+    let parse = |side: &str| -> HashSet<u32> {
+        side.split_whitespace()
+            .map(|n| n.parse::<u32>().unwrap())
+            .collect()
+    };
+    parse(winning).intersection(&parse(game)).count()
+}
+
+/// Puzzle-shaped cards, 10 winning numbers and 25 of ours, drawn without
+/// repeats from `1..=max_number` by a fixed linear congruential generator.
+fn generate(n_cards: u32, max_number: u32) -> String {
+    let mut state: u64 = 0x2023_1204;
+    let mut next = |bound: u32| -> u32 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % bound as u64) as u32 + 1
+    };
+
+    let mut input = String::new();
+    let mut side = Vec::new();
+    for id in 1..=n_cards {
+        write!(input, "Card {}:", id).unwrap();
+        for (count, separator) in [(10, " |"), (25, "\n")] {
+            side.clear();
+            while side.len() < count.min(max_number as usize) {
+                let number = next(max_number);
+                if !side.contains(&number) {
+                    side.push(number);
+                }
+            }
+            for number in &side {
+                write!(input, " {}", number).unwrap();
+            }
+            input.push_str(separator);
+        }
+    }
+    input
+}
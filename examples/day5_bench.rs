@@ -0,0 +1,113 @@
+//! Times brute-force day 5 part 2, seed by seed, with the linear scan of
+//! `grow_seed`, with `Map::get` and with the batch merge of `Route::get_all`,
+//! on the first seeds of the real input. Then times `grow_seed` against
+//! `Map::get` on random values over generated maps of growing size.
+//!
+//! On consecutive seeds the scan holds its own against a binary search, so
+//! `Map::get` only searches tables of more than `SCAN_LIMIT` entries; the batch
+//! merge is what beats the scan on the real input. On random values the
+//! binary search wins from a handful of entries up.
+//!
+//! usage: cargo run --release --example day5_bench -- [seeds]
+
+use adventofcode2023::day5::{grow_seed, Almanac, Map, MapEntry, SCAN_LIMIT};
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+pub fn main() {
+    let limit: u64 = env::args()
+        .nth(1)
+        .map_or(10_000_000, |n| n.parse().expect("invalid seed count"));
+
+    let input = fs::read_to_string("./inputs/day5.txt").expect("Failed to read input");
+    let almanac = Almanac::parse(&input);
+    let route = almanac
+        .route("seed", "location")
+        .expect("the almanac should lead from seed to location");
+    let chain = almanac.chain("seed", "location").unwrap();
+
+    let mut seeds = Vec::new();
+    for range in almanac.seed_ranges() {
+        let room = limit - seeds.len() as u64;
+        seeds.extend(range.take(room as usize));
+    }
+    println!("{} seeds", seeds.len());
+
+    let (linear, linear_time) = time(|| {
+        seeds
+            .iter()
+            .map(|&seed| chain.iter().fold(seed, |v, map| grow_seed(v, &map.entries)))
+            .min()
+    });
+    let (table, table_time) = time(|| seeds.iter().map(|&seed| route.get(seed)).min());
+    let (batch, batch_time) = time(|| route.get_all(seeds.clone()).first().copied());
+    assert_eq!(linear, table);
+    assert_eq!(linear, batch);
+
+    println!("minimum location {:?}", linear);
+    println!("linear scan:   {:?}", linear_time);
+    println!("Map::get:      {:?}", table_time);
+    println!("batch merge:   {:?}", batch_time);
+
+    println!(
+        "\n1000000 random values, tables scanned up to {} entries",
+        SCAN_LIMIT
+    );
+    for size in [8, 16, 32, 64, 256, 1024] {
+        let map = generated_map(size);
+        let span = size as u64 * 1000;
+        let values: Vec<u64> = lcg(size as u64)
+            .map(|value| value % span)
+            .take(1_000_000)
+            .collect();
+
+        let (linear, linear_time) = time(|| {
+            values
+                .iter()
+                .map(|&value| grow_seed(value, &map.entries))
+                .max()
+        });
+        let (table, table_time) = time(|| values.iter().map(|&value| map.get(value)).max());
+        assert_eq!(linear, table);
+        println!(
+            "{:>5} entries: linear scan {:?}, Map::get {:?}",
+            size, linear_time, table_time
+        );
+    }
+}
+
+/// `size` entries tiling `0..size * 1000` in a shuffled order, reversing the
+/// order of their ranges.
+fn generated_map(size: usize) -> Map {
+    let mut random = lcg(size as u64 * 7919);
+    let mut entries: Vec<MapEntry> = (0..size as u64)
+        .map(|i| MapEntry {
+            destination: (size as u64 - 1 - i) * 1000,
+            source: i * 1000,
+            range: 1000,
+        })
+        .collect();
+    for i in (1..entries.len()).rev() {
+        let j = random.next().unwrap() as usize % (i + 1);
+        entries.swap(i, j);
+    }
+    Map::new("seed", "location", entries)
+}
+
+/// A linear congruential generator, so runs are repeatable.
+fn lcg(seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        state >> 33
+    })
+}
+
+fn time<F: FnOnce() -> Option<u64>>(f: F) -> (Option<u64>, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
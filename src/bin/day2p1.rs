@@ -1,103 +1,144 @@
-use std::fs;
+use adventofcode2023::cli::{list, load_games, Flags};
+use adventofcode2023::day2::probability::{
+    draw_probability, game_probability, ln_draw_probability, ln_game_probability, rank_bags,
+};
+use adventofcode2023::day2::{Bag, Game, ParseOptions};
+use std::{env, process};
 
 // 12 red cubes, 13 green cubes, and 14 blue cubes
-const RED_CUBES: i32 = 12;
-const GREEN_CUBES: i32 = 13;
-const BLUE_CUBES: i32 = 14;
+const DEFAULT_BAG: &str = "red=12,green=13,blue=14";
 
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day2.txt").expect("Failed to read input");
+const USAGE: &str = "usage: day2p1 [check|probability] [--bag <color>=<count>,...] [parse options]
+       day2p1 rank --bag <color>=<count>,... [--bag ...] [parse options]
+parse options: [--mode strict|lenient] [--known-colors <color>,...]";
 
-    let mut sum: i32 = 0;
+#[derive(Debug, PartialEq)]
+enum Command {
+    Check,
+    Probability,
+    Rank,
+}
 
-    input.split("\n").for_each(|line| {
-        let x: Vec<&str> = line.split(':').collect();
-        if is_line_valid(x[1]) {
-            if let Some(id) = parse_game_id(x[0]) {
-                sum += id;
-            }
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    bags: Vec<Bag>,
+    parse: ParseOptions,
+}
+
+pub fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
         }
-    });
+    };
 
-    println!("{}", sum);
-}
+    let games = load_games(&options.parse);
 
-fn is_line_valid(line: &str) -> bool {
-    let mut max_red = 0;
-    let mut max_green = 0;
-    let mut max_blue = 0;
-
-    let draws = line.split(';');
-    for draw in draws {
-        let rolls = draw.split(',');
-        for roll in rolls {
-            if let Some((number, color)) = parse_number_and_color(roll) {
-                match color {
-                    "red" => {
-                        if number > max_red {
-                            max_red = number;
-                        }
-                    }
-                    "green" => {
-                        if number > max_green {
-                            max_green = number;
-                        }
-                    }
-                    "blue" => {
-                        if number > max_blue {
-                            max_blue = number;
-                        }
-                    }
-                    _ => println!("What color did you pass in?"),
+    match options.command {
+        Command::Check => {
+            let bag = &options.bags[0];
+            for game in &games {
+                for violation in bag.violations(game) {
+                    println!("Impossible: {}", violation);
                 }
-            } else {
-                println!("Something went wrong parsing the roll");
             }
-        }
-    }
 
-    if max_red > RED_CUBES {
-        false
-    } else if max_green > GREEN_CUBES {
-        false
-    } else if max_blue > BLUE_CUBES {
-        false
-    } else {
-        true
+            println!("{}", sum_possible_ids(&games, bag));
+        }
+        Command::Probability => print_probabilities(&games, &options.bags[0]),
+        Command::Rank => {
+            for (i, ranking) in rank_bags(&options.bags, &games).iter().enumerate() {
+                println!(
+                    "{}. {} (ln likelihood {:.3})",
+                    i + 1,
+                    ranking.bag,
+                    ranking.ln_likelihood
+                );
+            }
+        }
     }
 }
 
-fn parse_number_and_color(input: &str) -> Option<(i32, &str)> {
-    let mut iter = input.split_whitespace();
+fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let command = match flags.command(&["check", "probability", "rank"]).as_deref() {
+        Some("probability") => Command::Probability,
+        Some("rank") => Command::Rank,
+        _ => Command::Check,
+    };
+    let mut bags: Vec<String> = Vec::new();
+    let mut parse = ParseOptions::default();
+
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--bag" => bags.push(flags.value()?),
+            "--mode" => parse.mode = flags.value()?.parse()?,
+            "--known-colors" => parse.known_colors = Some(list(&flags.value()?)),
+            _ => return Err(format!("unexpected argument {:?}", flag)),
+        }
+    }
 
-    if let Some(number_str) = iter.next() {
-        if let Ok(number) = number_str.parse::<i32>() {
-            if let Some(color) = iter.next() {
-                return Some((number, color));
-            }
+    if bags.is_empty() {
+        if command == Command::Rank {
+            return Err("rank needs at least one --bag".to_string());
         }
+        bags.push(DEFAULT_BAG.to_string());
+    } else if bags.len() > 1 && command != Command::Rank {
+        return Err("only rank accepts more than one --bag".to_string());
     }
 
-    None
+    let bags = bags
+        .iter()
+        .map(|bag| bag.parse())
+        .collect::<Result<Vec<Bag>, _>>()
+        .map_err(|e| format!("invalid bag: {}", e))?;
+
+    Ok(Options {
+        command,
+        bags,
+        parse,
+    })
 }
 
-fn parse_game_id(input: &str) -> Option<i32> {
-    let mut iter = input.split_whitespace();
-
-    if let Some(_game_str) = iter.next() {
-        if let Some(game_id) = iter.next() {
-            if let Ok(n) = game_id.parse::<i32>() {
-                return Some(n);
-            }
+fn print_probabilities(games: &[Game], bag: &Bag) {
+    for game in games {
+        println!(
+            "Game {}: p = {} (ln p = {:.3})",
+            game.id,
+            game_probability(bag, game),
+            ln_game_probability(bag, game)
+        );
+        for (i, draw) in game.draws.iter().enumerate() {
+            println!(
+                "  draw {} [{}]: p = {} (ln p = {:.3})",
+                i + 1,
+                draw,
+                draw_probability(bag, draw),
+                ln_draw_probability(bag, draw)
+            );
         }
     }
+}
 
-    None
+fn sum_possible_ids(games: &[Game], bag: &Bag) -> u32 {
+    games
+        .iter()
+        .filter(|game| bag.is_possible(game))
+        .map(|game| game.id)
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day2::{parse_games, ParseMode};
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -109,28 +150,44 @@ mod tests {
     "};
 
     #[test]
-    fn test_parse_number_and_color() {
-        let mut r1 = parse_number_and_color("3 blue").unwrap();
-        assert_eq!(r1.0, 3);
-        assert_eq!(r1.1, "blue");
-
-        r1 = parse_number_and_color("13 green").unwrap();
-        assert_eq!(r1.0, 13);
-        assert_eq!(r1.1, "green");
-
-        r1 = parse_number_and_color("1 red").unwrap();
-        assert_eq!(r1.0, 1);
-        assert_eq!(r1.1, "red");
+    fn test_sum_possible_ids() {
+        let games = parse_games(INPUT).unwrap();
+        let options = parse_args(args(&[])).unwrap();
+        assert_eq!(sum_possible_ids(&games, &options.bags[0]), 8);
+
+        let options = parse_args(args(&["--bag", "red=20,green=13,blue=6"])).unwrap();
+        assert_eq!(sum_possible_ids(&games, &options.bags[0]), 11);
     }
 
     #[test]
-    fn test_is_line_valid() {
-        let tests: Vec<&str> = INPUT.split("\n").collect();
-
-        assert_eq!(is_line_valid(tests[0]), true);
-        assert_eq!(is_line_valid(tests[1]), true);
-        assert_eq!(is_line_valid(tests[2]), false);
-        assert_eq!(is_line_valid(tests[3]), false);
-        assert_eq!(is_line_valid(tests[4]), true);
+    fn test_parse_args() {
+        let options = parse_args(args(&["--bag=red=1,yellow=3"])).unwrap();
+        assert_eq!(options.command, Command::Check);
+        assert_eq!(options.bags[0].count("yellow"), 3);
+        assert_eq!(options.bags[0].count("blue"), 0);
+
+        let options = parse_args(args(&["probability"])).unwrap();
+        assert_eq!(options.command, Command::Probability);
+        assert_eq!(options.bags[0].count("blue"), 14);
+
+        let options = parse_args(args(&["rank", "--bag", "red=1", "--bag=red=2"])).unwrap();
+        assert_eq!(options.command, Command::Rank);
+        assert_eq!(options.bags.len(), 2);
+
+        let options = parse_args(args(&["--mode", "lenient", "--known-colors=red,blue"])).unwrap();
+        assert_eq!(options.parse.mode, ParseMode::Lenient);
+        assert_eq!(
+            options.parse.known_colors,
+            Some(vec!["red".to_string(), "blue".to_string()])
+        );
+
+        assert!(parse_args(args(&["--mode", "loose"])).is_err());
+        assert!(parse_args(args(&["rank"])).is_err());
+        assert!(parse_args(args(&["--bag", "red=1", "--bag", "red=2"])).is_err());
+        assert!(parse_args(args(&["--bag", "red=1", "rank"])).is_err());
+
+        assert!(parse_args(args(&["--bag"])).is_err());
+        assert!(parse_args(args(&["--bag", "red"])).is_err());
+        assert!(parse_args(args(&["--cubes"])).is_err());
     }
 }
@@ -1,105 +1,182 @@
-use std::fs;
-
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day2.txt").expect("Failed to read input");
-
-    let mut sum: i32 = 0;
+use adventofcode2023::cli::{list, load_games, Flags};
+use adventofcode2023::day2::{
+    minimum_bag, write_minimum_bags_csv, write_minimum_bags_json, Game, ParseOptions,
+};
+use std::{env, io, process};
+
+const DEFAULT_COLORS: &str = "red,green,blue";
+
+const USAGE: &str =
+    "usage: day2p2 [--colors <color>,...] [--games <id>,...] [--format text|csv|json]
+              [--mode strict|lenient] [--known-colors <color>,...]";
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
 
-    input.split("\n").for_each(|line| {
-        let x: Vec<&str> = line.split(':').collect();
-        sum += compute_power(x[1]);
-    });
+#[derive(Debug)]
+struct Options {
+    colors: Vec<String>,
+    games: Option<Vec<u32>>,
+    format: Format,
+    parse: ParseOptions,
+}
 
-    println!("{}", sum);
+pub fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let games = select_games(load_games(&options.parse), options.games.as_deref());
+    let colors: Vec<&str> = options.colors.iter().map(String::as_str).collect();
+
+    let mut stdout = io::stdout().lock();
+    match options.format {
+        Format::Csv => write_minimum_bags_csv(&mut stdout, &games, &colors),
+        Format::Json => write_minimum_bags_json(&mut stdout, &games, &colors),
+        Format::Text => {
+            if options.games.is_some() {
+                let bag = minimum_bag(&games);
+                println!("Minimum bag: {} (power {})", bag, bag.power_of(&colors));
+            }
+            println!("{}", sum_powers(&games, &colors));
+            Ok(())
+        }
+    }
+    .expect("Failed to write output");
 }
 
-fn compute_power(line: &str) -> i32 {
-    let mut max_red = 0;
-    let mut max_green = 0;
-    let mut max_blue = 0;
-
-    let draws = line.split(';');
-    for draw in draws {
-        let rolls = draw.split(',');
-        for roll in rolls {
-            if let Some((number, color)) = parse_number_and_color(roll) {
-                match color {
-                    "red" => {
-                        if number > max_red {
-                            max_red = number;
-                        }
-                    }
-                    "green" => {
-                        if number > max_green {
-                            max_green = number;
-                        }
-                    }
-                    "blue" => {
-                        if number > max_blue {
-                            max_blue = number;
-                        }
-                    }
-                    _ => println!("What color did you pass in?"),
+fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut options = Options {
+        colors: list(DEFAULT_COLORS),
+        games: None,
+        format: Format::Text,
+        parse: ParseOptions::default(),
+    };
+
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--colors" => options.colors = list(&flags.value()?),
+            "--games" => {
+                let ids = list(&flags.value()?)
+                    .iter()
+                    .map(|id| id.parse::<u32>())
+                    .collect::<Result<Vec<u32>, _>>()
+                    .map_err(|e| format!("invalid game id: {}", e))?;
+                options.games = Some(ids);
+            }
+            "--format" => {
+                options.format = match flags.value()?.as_str() {
+                    "text" => Format::Text,
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format {:?}", other)),
                 }
-            } else {
-                println!("Something went wrong parsing the roll");
             }
+            "--mode" => options.parse.mode = flags.value()?.parse()?,
+            "--known-colors" => options.parse.known_colors = Some(list(&flags.value()?)),
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
     }
 
-    max_red * max_green * max_blue
-}
+    if options.colors.is_empty() {
+        return Err("--colors needs at least one color".to_string());
+    }
 
-fn parse_number_and_color(input: &str) -> Option<(i32, &str)> {
-    let mut iter = input.split_whitespace();
+    Ok(options)
+}
 
-    if let Some(number_str) = iter.next() {
-        if let Ok(number) = number_str.parse::<i32>() {
-            if let Some(color) = iter.next() {
-                return Some((number, color));
-            }
-        }
+fn select_games(games: Vec<Game>, ids: Option<&[u32]>) -> Vec<Game> {
+    match ids {
+        Some(ids) => games
+            .into_iter()
+            .filter(|game| ids.contains(&game.id))
+            .collect(),
+        None => games,
     }
+}
 
-    None
+fn sum_powers(games: &[Game], colors: &[&str]) -> u64 {
+    games
+        .iter()
+        .map(|game| game.minimum_bag().power_of(colors))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day2::{parse_games, ParseMode};
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
-        3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-        1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
-        8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
-        1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
-        6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+        Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+        Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
     "};
 
+    const RGB: [&str; 3] = ["red", "green", "blue"];
+
     #[test]
-    fn test_parse_number_and_color() {
-        let mut r1 = parse_number_and_color("3 blue").unwrap();
-        assert_eq!(r1.0, 3);
-        assert_eq!(r1.1, "blue");
-
-        r1 = parse_number_and_color("13 green").unwrap();
-        assert_eq!(r1.0, 13);
-        assert_eq!(r1.1, "green");
-
-        r1 = parse_number_and_color("1 red").unwrap();
-        assert_eq!(r1.0, 1);
-        assert_eq!(r1.1, "red");
+    fn test_compute_power() {
+        let tests = parse_games(INPUT).unwrap();
+
+        assert_eq!(tests[0].minimum_bag().power_of(&RGB), 48);
+        assert_eq!(tests[1].minimum_bag().power_of(&RGB), 12);
+        assert_eq!(tests[2].minimum_bag().power_of(&RGB), 1560);
+        assert_eq!(tests[3].minimum_bag().power_of(&RGB), 630);
+        assert_eq!(tests[4].minimum_bag().power_of(&RGB), 36);
+        assert_eq!(sum_powers(&tests, &RGB), 2286);
     }
 
     #[test]
-    fn test_compute_power() {
-        let tests: Vec<&str> = INPUT.split("\n").collect();
+    fn test_select_games() {
+        let games = select_games(parse_games(INPUT).unwrap(), Some(&[1, 3]));
+        assert_eq!(games.len(), 2);
+        assert_eq!(sum_powers(&games, &["red"]), 24);
+        assert_eq!(minimum_bag(&games).power_of(&RGB), 20 * 13 * 6);
+    }
 
-        assert_eq!(compute_power(tests[0]), 48);
-        assert_eq!(compute_power(tests[1]), 12);
-        assert_eq!(compute_power(tests[2]), 1560);
-        assert_eq!(compute_power(tests[3]), 630);
-        assert_eq!(compute_power(tests[4]), 36);
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(&[])).unwrap();
+        assert_eq!(options.colors, RGB);
+        assert_eq!(options.games, None);
+        assert_eq!(options.format, Format::Text);
+
+        let options = parse_args(args(&[
+            "--colors=red,yellow",
+            "--games",
+            "1,5",
+            "--format",
+            "csv",
+        ]))
+        .unwrap();
+        assert_eq!(options.colors, ["red", "yellow"]);
+        assert_eq!(options.games, Some(vec![1, 5]));
+        assert_eq!(options.format, Format::Csv);
+
+        let options = parse_args(args(&["--mode=lenient", "--known-colors", "red"])).unwrap();
+        assert_eq!(options.parse.mode, ParseMode::Lenient);
+        assert_eq!(options.parse.known_colors, Some(vec!["red".to_string()]));
+
+        assert!(parse_args(args(&["--mode", "loose"])).is_err());
+        assert!(parse_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_args(args(&["--games", "one"])).is_err());
+        assert!(parse_args(args(&["--colors", ","])).is_err());
     }
 }
@@ -1,87 +1,115 @@
-use std::fs;
-
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day3.txt").expect("Failed to read input");
-    let total = compute_sum(&input);
-    println!("{}", total);
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day3::graph::PartGraph;
+use adventofcode2023::day3::{Rules, Schematic};
+use std::{env, fs, io, process};
+
+const USAGE: &str = "usage: day3p1 [--symbols punctuation|any|<chars>]
+       day3p1 graph [--format text|json|dot] [--symbols punctuation|any|<chars>]";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Sum,
+    Graph(Format),
 }
 
-fn compute_sum(schematic: &str) -> i32 {
-    let mut sum = 0;
-    // Collect our lines into a buffer; these will be our y values
-    let lines: Vec<&str> = schematic.lines().collect();
-
-    for (i, line) in lines.iter().enumerate() {
-        // Collect our characters; these will be our x values
-        let characters: Vec<char> = line.chars().collect();
-
-        let mut min: i32 = 0; // min value to check
-        let mut max: i32; // max value to check
-        let line_length: i32 = characters.len() as i32;
-
-        for (j, character) in characters.iter().enumerate() {
-            if character.is_digit(10) {
-                if j == 0 || !characters[j - 1].is_digit(10) {
-                    min = j as i32;
-                }
-
-                max = j as i32;
-
-                if j == characters.len() - 1 || !characters[j + 1].is_digit(10) {
-                    // check current line
-                    let mut is_valid = is_adjacent_symbol(lines[i], min, max, line_length);
-
-                    // check line above
-                    if i > 0 && !is_valid {
-                        is_valid = is_adjacent_symbol(lines[i - 1], min, max, line_length);
-                    }
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Dot,
+}
 
-                    // check line below
-                    if i + 1 < lines.len() && !is_valid {
-                        is_valid = is_adjacent_symbol(lines[i + 1], min, max, line_length);
-                    }
+pub fn main() {
+    let (command, rules) = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
 
-                    if is_valid {
-                        let slice = &characters[min as usize..=max as usize];
-                        sum += parse_number_from_char_array(slice);
-                    }
+    let input = fs::read_to_string("./inputs/day3.txt").expect("Failed to read input");
+    let schematic = Schematic::new(&input);
+
+    match command {
+        Command::Sum => match schematic.sum_part_numbers(&rules) {
+            Ok(sum) => println!("{}", sum),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        Command::Graph(format) => {
+            let graph = PartGraph::new(&schematic, &rules);
+            let mut stdout = io::stdout().lock();
+            match format {
+                Format::Text => {
+                    print_graph(&schematic, &graph);
+                    Ok(())
                 }
+                Format::Json => graph.write_json(&mut stdout),
+                Format::Dot => graph.write_dot(&mut stdout),
             }
+            .expect("Failed to write output");
         }
     }
-
-    return sum;
 }
 
-fn is_adjacent_symbol(row: &str, min: i32, max: i32, line_length: i32) -> bool {
-    for dx in min - 1..=max + 1 {
-        if dx > 0 && dx < line_length {
-            let nth_char = row.chars().nth(dx as usize).unwrap();
-            if nth_char.is_ascii_punctuation() && nth_char != '.' {
-                return true;
+fn parse_args<I>(args: I) -> Result<(Command, Rules), String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut command = match flags.command(&["graph"]) {
+        Some(_) => Command::Graph(Format::Text),
+        None => Command::Sum,
+    };
+    let mut rules = Rules::default();
+
+    while let Some(flag) = flags.next_flag() {
+        match (flag.as_str(), &mut command) {
+            ("--symbols", _) => rules.set("symbols", &flags.value()?)?,
+            ("--format", Command::Graph(format)) => {
+                *format = match flags.value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "dot" => Format::Dot,
+                    other => return Err(format!("unknown format {:?}", other)),
+                }
             }
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
     }
 
-    return false;
+    Ok((command, rules))
 }
 
-fn parse_number_from_char_array(buffer: &[char]) -> i32 {
-    let mut number = String::from("");
-    for digit in buffer {
-        number.push(*digit);
+fn print_graph(schematic: &Schematic, graph: &PartGraph) {
+    let parts = schematic.parts();
+    let values = |ids: &[usize]| -> String {
+        let values: Vec<String> = ids.iter().map(|&id| parts[id].value.clone()).collect();
+        values.join(", ")
+    };
+
+    for symbol in graph.symbols() {
+        println!(
+            "{} at ({}, {}): {}",
+            symbol.symbol,
+            symbol.row,
+            symbol.column,
+            values(&symbol.parts)
+        );
     }
-
-    if let Ok(parsed_number) = number.parse::<i32>() {
-        return parsed_number;
+    for (i, component) in graph.components().iter().enumerate() {
+        println!("Component {}: {}", i + 1, values(component));
     }
-
-    return 0;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day3::SymbolSet;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -99,6 +127,87 @@ mod tests {
 
     #[test]
     fn test_compute_sum() {
-        assert_eq!(compute_sum(INPUT), 4361)
+        assert_eq!(
+            Schematic::new(INPUT).sum_part_numbers(&Rules::default()),
+            Ok(4361)
+        )
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let (command, rules) = parse_args(args(&["--symbols", "#§"])).unwrap();
+        assert_eq!(command, Command::Sum);
+        assert_eq!(rules.symbols, SymbolSet::Chars(vec!['#', '§']));
+
+        let (command, _) = parse_args(args(&["graph"])).unwrap();
+        assert_eq!(command, Command::Graph(Format::Text));
+
+        let (command, rules) =
+            parse_args(args(&["graph", "--format=dot", "--symbols", "any"])).unwrap();
+        assert_eq!(command, Command::Graph(Format::Dot));
+        assert_eq!(rules.symbols, SymbolSet::Any);
+
+        assert!(parse_args(args(&["--format", "json"])).is_err());
+        assert!(parse_args(args(&["graph", "--format", "svg"])).is_err());
+        assert!(parse_args(args(&["--gears=*"])).is_err());
+        assert!(parse_args(args(&["--symbols"])).is_err());
+    }
+
+    #[test]
+    fn test_symbols_on_edges() {
+        // Symbol in column 0, next to a number on the row below and the row above
+        assert_eq!(
+            Schematic::new("12..\n#...\n.3..").sum_part_numbers(&Rules::default()),
+            Ok(15)
+        );
+        // Symbol in the last column
+        assert_eq!(
+            Schematic::new("..12\n...#\n..30").sum_part_numbers(&Rules::default()),
+            Ok(42)
+        );
+        // Symbols in the first and last rows
+        assert_eq!(
+            Schematic::new("..$.\n.7..\n....\n..8.\n.%..").sum_part_numbers(&Rules::default()),
+            Ok(15)
+        );
+    }
+
+    #[test]
+    fn test_numbers_in_corners() {
+        let schematic = Schematic::new(indoc! {"
+            1.....2
+            .*...+.
+            .......
+            .&...=.
+            3.....4
+        "});
+        assert_eq!(schematic.sum_part_numbers(&Rules::default()), Ok(10));
+
+        // Same corners with the symbols moved out of reach
+        let schematic = Schematic::new(indoc! {"
+            1.....2
+            ..*.+..
+            .......
+            ..&.=..
+            3.....4
+        "});
+        assert_eq!(schematic.sum_part_numbers(&Rules::default()), Ok(0));
+    }
+
+    #[test]
+    fn test_unequal_rows() {
+        // The symbol sits past the end of the row above it
+        assert_eq!(
+            Schematic::new("..5\n...*").sum_part_numbers(&Rules::default()),
+            Ok(5)
+        );
+        assert_eq!(
+            Schematic::new("..5\n.\n...*").sum_part_numbers(&Rules::default()),
+            Ok(0)
+        );
+        assert_eq!(
+            Schematic::new("1\n..*.\n...99").sum_part_numbers(&Rules::default()),
+            Ok(99)
+        );
     }
 }
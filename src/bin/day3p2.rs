@@ -1,100 +1,113 @@
-use std::fs;
-
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day3.txt").expect("Failed to read input");
-    let part_info = read_data(&input);
-    let sum = find_gear_ratio(&input, &part_info);
-    println!("{}", sum);
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day3::render::{write_ansi, write_html};
+use adventofcode2023::day3::{Rules, Schematic};
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::{env, fs, process};
+
+const USAGE: &str = "usage: day3p2 [rule options]
+       day3p2 render [--format ansi|html] [--output <file>] [rule options]
+rule options: [--symbols punctuation|any|<chars>] [--gears <chars>]
+              [--arity <n>|<n>+] [--ratio product|sum]";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Sum,
+    /// Colours by default when writing to a terminal, HTML otherwise
+    Render {
+        format: Option<Format>,
+        output: Option<String>,
+    },
 }
 
-struct PartInfo {
-    column_index: usize,
-    row_index: usize,
-    length: usize,
-    value: String,
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Format {
+    Ansi,
+    Html,
 }
 
-fn read_data(schematic: &str) -> Vec<PartInfo> {
-    let mut parts: Vec<PartInfo> = Vec::new();
-
-    for (row_index, line) in schematic.lines().enumerate() {
-        let mut start_index: Option<usize> = None;
-        let mut length: usize = 0;
-        let mut value = String::new();
-
-        for (x, char) in line.chars().enumerate() {
-            if char.is_numeric() {
-                if start_index.is_none() {
-                    start_index = Some(x);
-                }
-                length += 1;
-                value.push(char);
-            } else if start_index.is_some() {
-                let start_index_value = start_index.unwrap();
-                parts.push(PartInfo {
-                    column_index: start_index_value,
-                    row_index,
-                    length,
-                    value: value.clone(),
-                });
-
-                start_index = None;
-                length = 0;
-                value.clear();
-            }
+pub fn main() {
+    let (command, rules) = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
         }
+    };
 
-        if start_index.is_some() {
-            let start_index_value = start_index.unwrap();
-            parts.push(PartInfo {
-                column_index: start_index_value,
-                row_index,
-                length,
-                value: value.clone(),
+    let input = fs::read_to_string("./inputs/day3.txt").expect("Failed to read input");
+    let schematic = Schematic::new(&input);
+
+    match command {
+        Command::Sum => match schematic.sum_gear_ratios(&rules) {
+            Ok(sum) => println!("{}", sum),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        Command::Render { format, output } => {
+            let mut out: Box<dyn Write> = match &output {
+                Some(path) => Box::new(File::create(path).expect("Failed to create output file")),
+                None => Box::new(io::stdout().lock()),
+            };
+            let to_terminal = output.is_none() && io::stdout().is_terminal();
+            let format = format.unwrap_or(if to_terminal {
+                Format::Ansi
+            } else {
+                Format::Html
             });
+
+            match format {
+                Format::Ansi => write_ansi(&mut out, &schematic, &rules),
+                Format::Html => write_html(&mut out, &schematic, &rules),
+            }
+            .expect("Failed to write output");
         }
     }
-
-    parts
 }
 
-fn find_gear_ratio(schematic: &str, part_info: &Vec<PartInfo>) -> i32 {
-    let mut sum = 0;
-
-    for (row_index, line) in schematic.lines().enumerate() {
-        for (col_index, char) in line.chars().enumerate() {
-            if char == '*' {
-                let mut adjacent_numbers: Vec<&PartInfo> = Vec::new();
-                for part in part_info {
-                    if part.row_index > row_index + 1 || part.row_index < row_index - 1 {
-                        continue;
-                    }
-
-                    if part.column_index == col_index
-                        || part.column_index == col_index - 1
-                        || part.column_index == col_index + 1
-                        || part.column_index + part.length == col_index
-                        || part.column_index + part.length == col_index + 1
-                    {
-                        adjacent_numbers.push(part);
-                    }
-                }
-
-                if adjacent_numbers.len() == 2 {
-                    let v1 = adjacent_numbers[0].value.parse::<i32>().unwrap();
-                    let v2 = adjacent_numbers[1].value.parse::<i32>().unwrap();
-                    sum += v1 * v2;
+fn parse_args<I>(args: I) -> Result<(Command, Rules), String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut command = match flags.command(&["render"]) {
+        Some(_) => Command::Render {
+            format: None,
+            output: None,
+        },
+        None => Command::Sum,
+    };
+    let mut rules = Rules::default();
+
+    while let Some(flag) = flags.next_flag() {
+        let Some(name) = flag.strip_prefix("--") else {
+            return Err(format!("unexpected argument {:?}", flag));
+        };
+        let value = flags.value()?;
+
+        match (name, &mut command) {
+            ("format", Command::Render { format, .. }) => {
+                *format = match value.as_str() {
+                    "ansi" => Some(Format::Ansi),
+                    "html" => Some(Format::Html),
+                    other => return Err(format!("unknown format {:?}", other)),
                 }
             }
+            ("output", Command::Render { output, .. }) => *output = Some(value),
+            _ => rules.set(name, &value)?,
         }
     }
 
-    return sum;
+    Ok((command, rules))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day3::Arity;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -115,8 +128,67 @@ mod tests {
 
     #[test]
     fn test_compute_sum() {
-        let part_info = read_data(INPUT);
-        let sum = find_gear_ratio(INPUT, &part_info);
-        assert_eq!(sum, 821460)
+        let schematic = Schematic::new(INPUT);
+        let sum = schematic.sum_gear_ratios(&Rules::default());
+        assert_eq!(sum, Ok(821460))
+    }
+
+    #[test]
+    fn test_variants() {
+        let schematic = Schematic::new(INPUT);
+
+        let (_, rules) = parse_args(args(&["--arity", "1+", "--ratio=sum"])).unwrap();
+        assert_eq!(
+            schematic.sum_gear_ratios(&rules),
+            Ok(467 + 35 + 617 + 755 + 598 + 615 + 575)
+        );
+
+        let (_, rules) = parse_args(args(&["--gears", "*$#", "--arity", "1"])).unwrap();
+        assert_eq!(schematic.sum_gear_ratios(&rules), Ok(617 + 664 + 633));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let (command, rules) = parse_args(args(&["--arity", "3"])).unwrap();
+        assert_eq!(command, Command::Sum);
+        assert_eq!(rules.arity, Arity::Exactly(3));
+
+        let (command, _) =
+            parse_args(args(&["render", "--format=html", "--output", "x.html"])).unwrap();
+        assert_eq!(
+            command,
+            Command::Render {
+                format: Some(Format::Html),
+                output: Some("x.html".to_string())
+            }
+        );
+
+        assert!(parse_args(args(&["--format", "html"])).is_err());
+        assert!(parse_args(args(&["render", "--format", "svg"])).is_err());
+        assert!(parse_args(args(&["--arity", "two"])).is_err());
+        assert!(parse_args(args(&["--colour", "red"])).is_err());
+        assert!(parse_args(args(&["arity"])).is_err());
+    }
+
+    #[test]
+    fn test_gears_on_borders() {
+        // Left and right edges
+        let schematic = Schematic::new("2....5\n*....*\n3....4");
+        assert_eq!(schematic.sum_gear_ratios(&Rules::default()), Ok(6 + 20));
+
+        // Top and bottom edges, including corners
+        let schematic = Schematic::new("*2..7*\n3....6\n......\n8....1\n*9..2*");
+        assert_eq!(
+            schematic.sum_gear_ratios(&Rules::default()),
+            Ok(6 + 42 + 72 + 2)
+        );
+
+        // Gear sharing a row with both numbers
+        let schematic = Schematic::new("12*34");
+        assert_eq!(schematic.sum_gear_ratios(&Rules::default()), Ok(408));
+
+        // A gear with three neighbours isn't a gear
+        let schematic = Schematic::new("1.2\n.*.\n..3");
+        assert_eq!(schematic.sum_gear_ratios(&Rules::default()), Ok(0));
     }
 }
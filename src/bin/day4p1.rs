@@ -1,95 +1,54 @@
-use std::collections::HashSet;
-use std::fmt;
-use std::fs;
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day4::{scan_cards, Scoring};
+use std::{env, fs, process};
 
-pub fn main() {
-    let mut sum: u32 = 0;
-    let input = fs::read_to_string("./inputs/day4.txt").expect("Failed to read input");
-
-    for line in input.lines() {
-        let card = parse_line(line);
-        sum += card.calculate_points();
-    }
+const USAGE: &str = "usage: day4p1 [--scoring doubling|linear|table:0,1,3,...]";
 
-    println!("{}", sum);
-}
-
-struct Card {
-    card_id: String,
-    winning_numbers: HashSet<u32>,
-    game_numbers: HashSet<u32>,
-}
-
-impl Card {
-    pub fn new(card_id: &str, winning_numbers: HashSet<u32>, game_numbers: HashSet<u32>) -> Card {
-        Card {
-            card_id: card_id.to_string(),
-            winning_numbers,
-            game_numbers,
+pub fn main() {
+    let scoring = match parse_args(env::args().skip(1)) {
+        Ok(scoring) => scoring,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
         }
-    }
+    };
 
-    pub fn calculate_points(&self) -> u32 {
-        let base: u32 = 2;
-        let n_intersect = self.winning_numbers.intersection(&self.game_numbers);
-        let exp = n_intersect.count() as u32;
-        if exp == 0 {
-            return 0;
-        }
+    let input = fs::read_to_string("./inputs/day4.txt").expect("Failed to read input");
 
-        base.pow(exp - 1)
-    }
-}
+    let cards = match scan_cards(&input) {
+        Ok(cards) => cards,
+        Err(e) => {
+            eprintln!("Failed to parse cards: {}", e);
+            process::exit(1);
+        }
+    };
+    let sum: u64 = cards.iter().map(|card| card.score(&scoring)).sum();
 
-impl fmt::Display for Card {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "ID: {}\nWinning Numbers: {:?}\nGame Numbers: {:?}",
-            self.card_id, self.winning_numbers, self.game_numbers
-        )
-    }
+    println!("{}", sum);
 }
 
-fn parse_line(line: &str) -> Card {
-    // Get the card number
-    let parts: Vec<&str> = line.split(':').collect();
-    if parts.len() != 2 {
-        panic!("Failed to parse card number for: {}", line);
-    }
-
-    let card_id = parts[0];
-    let card_values = parts[1];
-
-    // Split the line by "|"
-    let parts: Vec<&str> = card_values.split('|').collect();
-    let mut winning_numbers = HashSet::new();
-    let mut game_numbers = HashSet::new();
-
-    if parts.len() != 2 {
-        panic!("Failed to parse value information for: {}", line);
-    }
-
-    // Parse numbers on the left side
-    for num_str in parts[0].trim().split_whitespace() {
-        if let Ok(num) = num_str.parse::<u32>() {
-            winning_numbers.insert(num);
-        }
-    }
-
-    // Parse numbers on the right side
-    for num_str in parts[1].trim().split_whitespace() {
-        if let Ok(num) = num_str.parse::<u32>() {
-            game_numbers.insert(num);
+fn parse_args<I>(args: I) -> Result<Scoring, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut scoring = Scoring::default();
+
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--scoring" => scoring = flags.value()?.parse()?,
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
     }
 
-    Card::new(card_id, winning_numbers, game_numbers)
+    Ok(scoring)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day4::parse_line;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -105,22 +64,38 @@ mod tests {
     fn test_compute_points() {
         let tests: Vec<&str> = INPUT.split("\n").collect();
 
-        let card = parse_line(tests[0]);
-        assert_eq!(card.calculate_points(), 8);
+        let card = parse_line(tests[0]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 8);
 
-        let card = parse_line(tests[1]);
-        assert_eq!(card.calculate_points(), 2);
+        let card = parse_line(tests[1]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 2);
 
-        let card = parse_line(tests[2]);
-        assert_eq!(card.calculate_points(), 2);
+        let card = parse_line(tests[2]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 2);
 
-        let card = parse_line(tests[3]);
-        assert_eq!(card.calculate_points(), 1);
+        let card = parse_line(tests[3]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 1);
 
-        let card = parse_line(tests[4]);
-        assert_eq!(card.calculate_points(), 0);
+        let card = parse_line(tests[4]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 0);
 
-        let card = parse_line(tests[5]);
-        assert_eq!(card.calculate_points(), 0);
+        let card = parse_line(tests[5]).unwrap();
+        assert_eq!(card.score(&Scoring::Doubling), 0);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])), Ok(Scoring::Doubling));
+        assert_eq!(
+            parse_args(args(&["--scoring", "linear"])),
+            Ok(Scoring::Linear)
+        );
+        assert_eq!(
+            parse_args(args(&["--scoring=table:0,1,5"])),
+            Ok(Scoring::Table(vec![0, 1, 5]))
+        );
+        assert!(parse_args(args(&["--scoring", "cubic"])).is_err());
+        assert!(parse_args(args(&["--scoring"])).is_err());
+        assert!(parse_args(args(&["linear"])).is_err());
     }
 }
@@ -1,105 +1,153 @@
-use std::collections::HashSet;
-use std::fmt;
-use std::fs;
-
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day4.txt").expect("Failed to read input");
-
-    let cards: Vec<Card> = scan_cards(&input);
-    let total_number_cards = count_cards(cards);
-    println!("Total cards: {}", total_number_cards);
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day4::provenance::Provenance;
+use adventofcode2023::day4::{count_cards, scan_cards, validate_table, Card, CopyRule, EndPolicy};
+use std::{env, fs, io, process};
+
+const USAGE: &str = "usage: day4p2 [--copies next|offsets:1,2,...] [--past-end clamp|error]
+       day4p2 analyze [--format text|json|dot] [--copies ...] [--past-end ...]";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Count,
+    Analyze(Format),
 }
 
-fn scan_cards(input: &str) -> Vec<Card> {
-    input.lines().map(|line| parse_line(line)).collect()
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Dot,
 }
 
-fn count_cards(cards: Vec<Card>) -> u32 {
-    let mut n_cards: Vec<u32> = vec![1; cards.len()];
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    rule: CopyRule,
+    policy: EndPolicy,
+}
 
-    for (i, card) in cards.iter().enumerate() {
-        let new_cards = card.calculate_points();
-        for _ in 0..n_cards[i] {
-            for j in 1..=new_cards as usize {
-                n_cards[j + i] += 1;
-            }
+pub fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
         }
-    }
+    };
 
-    n_cards.iter().sum()
-}
-
-struct Card {
-    card_id: String,
-    winning_numbers: HashSet<u32>,
-    game_numbers: HashSet<u32>,
-}
+    let input = fs::read_to_string("./inputs/day4.txt").expect("Failed to read input");
 
-impl Card {
-    pub fn new(card_id: &str, winning_numbers: HashSet<u32>, game_numbers: HashSet<u32>) -> Card {
-        Card {
-            card_id: card_id.to_string(),
-            winning_numbers,
-            game_numbers,
+    let cards = match scan_cards(&input) {
+        Ok(cards) => cards,
+        Err(e) => {
+            eprintln!("Failed to parse cards: {}", e);
+            process::exit(1);
         }
+    };
+    if let Err(e) = validate_table(&cards) {
+        eprintln!("Invalid card table: {}", e);
+        process::exit(1);
     }
 
-    pub fn calculate_points(&self) -> u32 {
-        let n_intersect = self.winning_numbers.intersection(&self.game_numbers);
-        n_intersect.count() as u32
+    match options.command {
+        Command::Count => match count_cards(&cards, &options.rule, options.policy) {
+            Ok(total_number_cards) => println!("Total cards: {}", total_number_cards),
+            Err(e) => {
+                eprintln!("Failed to count cards: {}", e);
+                process::exit(1);
+            }
+        },
+        Command::Analyze(format) => analyze(&cards, &options.rule, options.policy, format),
     }
 }
 
-impl fmt::Display for Card {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "ID: {}\nWinning Numbers: {:?}\nGame Numbers: {:?}",
-            self.card_id, self.winning_numbers, self.game_numbers
-        )
+fn analyze(cards: &[Card], rule: &CopyRule, policy: EndPolicy, format: Format) {
+    let provenance = match Provenance::new(cards, rule, policy) {
+        Ok(provenance) => provenance,
+        Err(e) => {
+            eprintln!("Failed to count cards: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut stdout = io::stdout().lock();
+    match format {
+        Format::Text => {
+            print_provenance(&provenance);
+            Ok(())
+        }
+        Format::Json => provenance.write_json(&mut stdout),
+        Format::Dot => provenance.write_dot(&mut stdout),
     }
+    .expect("Failed to write output");
 }
 
-fn parse_line(line: &str) -> Card {
-    // Get the card number
-    let parts: Vec<&str> = line.split(':').collect();
-    if parts.len() != 2 {
-        panic!("Failed to parse card number for: {}", line);
+fn print_provenance(provenance: &Provenance) {
+    for node in provenance.nodes() {
+        println!(
+            "Card {}: {} matches, {} copies, accounts for {} cards, chain depth {}",
+            node.card_id, node.matches, node.copies, node.contribution, node.depth
+        );
     }
 
-    let card_id = parts[0];
-    let card_values = parts[1];
-
-    // Split the line by "|"
-    let parts: Vec<&str> = card_values.split('|').collect();
-    let mut winning_numbers = HashSet::new();
-    let mut game_numbers = HashSet::new();
+    let chain: Vec<String> = provenance
+        .longest_chain()
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+    println!("Longest chain: {}", chain.join(" -> "));
 
-    if parts.len() != 2 {
-        panic!("Failed to parse value information for: {}", line);
-    }
-
-    // Parse numbers on the left side
-    for num_str in parts[0].trim().split_whitespace() {
-        if let Ok(num) = num_str.parse::<u32>() {
-            winning_numbers.insert(num);
-        }
+    for (matches, count) in provenance.histogram() {
+        println!("{} matches: {} cards", matches, count);
     }
+    println!("Total cards: {}", provenance.total());
+}
 
-    // Parse numbers on the right side
-    for num_str in parts[1].trim().split_whitespace() {
-        if let Ok(num) = num_str.parse::<u32>() {
-            game_numbers.insert(num);
+fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut options = Options {
+        command: match flags.command(&["analyze"]) {
+            Some(_) => Command::Analyze(Format::Text),
+            None => Command::Count,
+        },
+        rule: CopyRule::default(),
+        policy: EndPolicy::default(),
+    };
+
+    while let Some(flag) = flags.next_flag() {
+        match (flag.as_str(), &mut options.command) {
+            ("--copies", _) => options.rule = flags.value()?.parse()?,
+            ("--format", Command::Analyze(format)) => {
+                *format = match flags.value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "dot" => Format::Dot,
+                    other => return Err(format!("unknown format {:?}", other)),
+                }
+            }
+            ("--past-end", _) => {
+                options.policy = match flags.value()?.as_str() {
+                    "clamp" => EndPolicy::Clamp,
+                    "error" => EndPolicy::Error,
+                    other => return Err(format!("unknown policy {:?}", other)),
+                }
+            }
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
     }
 
-    Card::new(card_id, winning_numbers, game_numbers)
+    Ok(options)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
     use indoc::indoc;
+    use num::BigUint;
 
     const INPUT: &str = indoc! {"
         Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -112,8 +160,52 @@ mod tests {
 
     #[test]
     fn test_compute_points() {
-        let cards: Vec<Card> = scan_cards(INPUT);
-        let total_number_cards = count_cards(cards);
-        assert_eq!(total_number_cards, 30);
+        let cards = scan_cards(INPUT).unwrap();
+        let total_number_cards = count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp).unwrap();
+        assert_eq!(total_number_cards, BigUint::from(30u32));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = |rule: CopyRule, policy: EndPolicy| Options {
+            command: Command::Count,
+            rule,
+            policy,
+        };
+
+        assert_eq!(
+            parse_args(args(&[])),
+            Ok(options(CopyRule::Next, EndPolicy::Clamp))
+        );
+        assert_eq!(
+            parse_args(args(&["--past-end", "error"])),
+            Ok(options(CopyRule::Next, EndPolicy::Error))
+        );
+        assert_eq!(
+            parse_args(args(&["--past-end=clamp", "--copies=offsets:2,4"])),
+            Ok(options(CopyRule::Offsets(vec![2, 4]), EndPolicy::Clamp))
+        );
+        assert!(parse_args(args(&["--past-end", "wrap"])).is_err());
+        assert!(parse_args(args(&["--past-end"])).is_err());
+        assert!(parse_args(args(&["--copies", "offsets:0"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_analyze() {
+        assert_eq!(
+            parse_args(args(&["analyze", "--format", "dot", "--past-end=error"])),
+            Ok(Options {
+                command: Command::Analyze(Format::Dot),
+                rule: CopyRule::Next,
+                policy: EndPolicy::Error,
+            })
+        );
+        assert_eq!(
+            parse_args(args(&["analyze"])).map(|options| options.command),
+            Ok(Command::Analyze(Format::Text))
+        );
+        assert!(parse_args(args(&["--format", "json"])).is_err());
+        assert!(parse_args(args(&["--copies", "next", "analyze"])).is_err());
+        assert!(parse_args(args(&["analyze", "--format", "csv"])).is_err());
     }
 }
@@ -1,118 +1,210 @@
-use std::{fs, u64::MAX};
+use adventofcode2023::cli::{load_almanac, Flags};
+use adventofcode2023::day5::report::{best_seeds, SeedPath};
+use adventofcode2023::day5::validate::ValidationMode;
+use adventofcode2023::day5::{Almanac, ChainError};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::{env, process};
 
-pub fn main() {
-    let input = fs::read_to_string("./inputs/day5.txt").expect("Failed to read input");
-    let minimum_location = find_minimum_location(&input);
-    println!("Minimum location: {}", minimum_location);
+const USAGE: &str = "usage: day5p1 [--mode strict|lenient] [--top <count>]
+       day5p1 map [--from <category>] [--to <category>] <value>...
+       day5p1 inverse [--from <category>] [--to <category>] <value|start..end>...
+       day5p1 compose [--from <category>] [--to <category>] [--format text|json]";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    /// Report the `top` seeds with the lowest locations.
+    Minimum { top: usize },
+    Map {
+        from: String,
+        to: String,
+        values: Vec<u64>,
+    },
+    Inverse {
+        from: String,
+        to: String,
+        ranges: Vec<Range<u64>>,
+    },
+    Compose {
+        from: String,
+        to: String,
+        format: Format,
+    },
 }
 
-#[derive(Debug)]
-struct MapEntry {
-    destination: u64,
-    source: u64,
-    range: u64,
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    mode: ValidationMode,
 }
 
-fn read_seeds<'a, I>(mut lines: I) -> (Vec<u64>, I)
-where
-    I: Iterator<Item = &'a str>,
-{
-    let seeds: Vec<u64> = lines
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(|s| s.parse().unwrap())
-        .collect();
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
 
-    // Consume the empty line after as well to make my life easier
-    lines.next();
+pub fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let almanac = load_almanac(options.mode);
+    if let Err(e) = run(&almanac, options.command) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
 
-    (seeds, lines)
+fn run(almanac: &Almanac, command: Command) -> Result<(), ChainError> {
+    match command {
+        Command::Minimum { top } => {
+            let best = best_seeds(almanac, top)?;
+            write_best(&mut io::stdout().lock(), &best).expect("Failed to write output");
+        }
+        Command::Map { from, to, values } => {
+            let composed = almanac.compose(&from, &to)?;
+            for value in values {
+                println!("{} {} -> {} {}", from, value, to, composed.get(value));
+            }
+        }
+        Command::Inverse { from, to, ranges } => {
+            for range in ranges {
+                let sources = almanac.preimage(&from, &to, std::slice::from_ref(&range))?;
+                println!(
+                    "{} {} <- {} {}",
+                    to,
+                    format_ranges(std::slice::from_ref(&range)),
+                    from,
+                    format_ranges(&sources)
+                );
+            }
+        }
+        Command::Compose { from, to, format } => {
+            let composed = almanac.compose(&from, &to)?;
+            let mut stdout = io::stdout().lock();
+            match format {
+                Format::Text => composed.write_text(&mut stdout),
+                Format::Json => composed.write_json(&mut stdout),
+            }
+            .expect("Failed to write output");
+        }
+    }
+    Ok(())
 }
 
-fn read_map<'a, I>(mut lines: I) -> (Vec<MapEntry>, I)
+fn parse_args<I>(args: I) -> Result<Options, String>
 where
-    I: Iterator<Item = &'a str>,
+    I: Iterator<Item = String>,
 {
-    let _name = lines.next().unwrap().trim_end_matches(':');
-    let mut map_values: Vec<MapEntry> = Vec::new();
+    let mut flags = Flags::new(args);
+    let name = flags
+        .command(&["map", "inverse", "compose"])
+        .unwrap_or("minimum".to_string());
 
-    while let Some(line) = lines.next() {
-        if line.trim().is_empty() {
-            break;
+    let mut mode = ValidationMode::default();
+    let mut from = "seed".to_string();
+    let mut to = "location".to_string();
+    let mut values = Vec::new();
+    let mut format = Format::Text;
+    let mut top = 1;
+    while let Some(flag) = flags.next_flag() {
+        match (flag.as_str(), name.as_str()) {
+            ("--mode", _) => mode = flags.value()?.parse()?,
+            ("--top", "minimum") => {
+                top = match flags.value()?.parse() {
+                    Ok(0) | Err(_) => return Err("--top needs a positive count".to_string()),
+                    Ok(top) => top,
+                }
+            }
+            ("--from", "map" | "inverse" | "compose") => from = flags.value()?,
+            ("--to", "map" | "inverse" | "compose") => to = flags.value()?,
+            ("--format", "compose") => {
+                format = match flags.value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format {:?}", other)),
+                }
+            }
+            (_, "map" | "inverse") => match parse_range(&flag) {
+                Some(range) => values.push(range),
+                None => return Err(format!("unexpected argument {:?}", flag)),
+            },
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
-
-        let entries: Vec<u64> = line
-            .split_whitespace()
-            .map(|s| s.parse().unwrap())
-            .collect();
-
-        map_values.push(MapEntry {
-            destination: entries[0],
-            source: entries[1],
-            range: entries[2],
-        });
     }
 
-    (map_values, lines)
+    let command = match name.as_str() {
+        "minimum" => Command::Minimum { top },
+        "compose" => Command::Compose { from, to, format },
+        _ if values.is_empty() => return Err(format!("{} needs at least one value", name)),
+        "inverse" => Command::Inverse {
+            from,
+            to,
+            ranges: values,
+        },
+        _ => {
+            // Only `inverse` takes ranges
+            let values = values
+                .into_iter()
+                .map(|range| match range.end - range.start {
+                    1 => Ok(range.start),
+                    _ => Err(format!("map takes single values, not {:?}", range)),
+                })
+                .collect::<Result<Vec<u64>, String>>()?;
+            Command::Map { from, to, values }
+        }
+    };
+
+    Ok(Options { command, mode })
 }
 
-// Not sure if this is idiomatic Rust, but I liked the idea of passing an iterator back to
-// the same function so that I can just keep reading from the same thing until its used up and
-// all the map data is stored in variables for me to reference later ¯\_(ツ)_/¯
-fn find_minimum_location(input: &str) -> u64 {
-    // Remove the blank lines
-    let lines = input.lines();
-
-    // Read in the seeds
-    let (seeds, lines) = read_seeds(lines);
-
-    // Read in the maps
-    // A smarter person might read this in to a Vec<Vec> and make it more readable
-    // to create them and then loop through them. Maybe I'll do that later
-    let (seed_soil_map, lines) = read_map(lines);
-    let (soil_fertilizer_map, lines) = read_map(lines);
-    let (fertilizer_water_map, lines) = read_map(lines);
-    let (water_light_map, lines) = read_map(lines);
-    let (light_temperature_map, lines) = read_map(lines);
-    let (temperature_humidity_map, lines) = read_map(lines);
-    let (humidity_location_map, _) = read_map(lines);
-
-    let mut min_location: u64 = MAX;
-    for seed in seeds {
-        let mut plant = seed;
-        plant = grow_seed(plant, &seed_soil_map);
-        plant = grow_seed(plant, &soil_fertilizer_map);
-        plant = grow_seed(plant, &fertilizer_water_map);
-        plant = grow_seed(plant, &water_light_map);
-        plant = grow_seed(plant, &light_temperature_map);
-        plant = grow_seed(plant, &temperature_humidity_map);
-        plant = grow_seed(plant, &humidity_location_map);
-
-        if plant < min_location {
-            min_location = plant;
+/// `79` or `79..93`.
+fn parse_range(s: &str) -> Option<Range<u64>> {
+    match s.split_once("..") {
+        Some((start, end)) => {
+            let range = start.parse().ok()?..end.parse().ok()?;
+            (!range.is_empty()).then_some(range)
+        }
+        None => {
+            let value: u64 = s.parse().ok()?;
+            Some(value..value.checked_add(1)?)
         }
     }
+}
 
-    min_location
+fn format_ranges(ranges: &[Range<u64>]) -> String {
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|range| match range.end - range.start {
+            1 => range.start.to_string(),
+            _ => format!("{}..{}", range.start, range.end),
+        })
+        .collect();
+    ranges.join(", ")
 }
 
-fn grow_seed(seed: u64, map: &Vec<MapEntry>) -> u64 {
-    let mut sprout = seed;
-    for entry in map {
-        if seed >= entry.source && seed < entry.source + entry.range {
-            sprout = seed - entry.source + entry.destination;
-            break;
-        }
-    }
+fn find_minimum_location(best: &[SeedPath]) -> u64 {
+    best.first().map_or(u64::MAX, SeedPath::location)
+}
 
-    sprout
+/// The minimum location, then each seed in `best` through every category.
+fn write_best<W: Write>(out: &mut W, best: &[SeedPath]) -> io::Result<()> {
+    writeln!(out, "Minimum location: {}", find_minimum_location(best))?;
+    for (i, path) in best.iter().enumerate() {
+        writeln!(out, "#{} {}", i + 1, path)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -153,6 +245,103 @@ mod tests {
 
     #[test]
     fn test_parse_map() {
-        assert_eq!(find_minimum_location(INPUT), 35);
+        let best = best_seeds(&Almanac::parse(INPUT), 1).unwrap();
+        assert_eq!(find_minimum_location(&best), 35);
+    }
+
+    #[test]
+    fn test_write_best() {
+        let best = best_seeds(&Almanac::parse(INPUT), 2).unwrap();
+        let mut out = Vec::new();
+        write_best(&mut out, &best).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            indoc! {"
+                Minimum location: 35
+                #1 seed 13 -> soil 13 -> fertilizer 52 -> water 41 -> light 34 -> temperature 34 -> humidity 35 -> location 35
+                #2 seed 14 -> soil 14 -> fertilizer 53 -> water 49 -> light 42 -> temperature 42 -> humidity 43 -> location 43
+            "}
+        );
+    }
+
+    fn command(a: &[&str]) -> Result<Command, String> {
+        parse_args(args(a)).map(|options| options.command)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(command(&[]), Ok(Command::Minimum { top: 1 }));
+        assert_eq!(command(&["--top=3"]), Ok(Command::Minimum { top: 3 }));
+        assert!(command(&["--top", "0"]).is_err());
+        assert!(command(&["map", "--top", "3", "79"]).is_err());
+        assert_eq!(
+            command(&["map", "--from", "seed", "--to=humidity", "79", "14"]),
+            Ok(Command::Map {
+                from: "seed".to_string(),
+                to: "humidity".to_string(),
+                values: vec![79, 14]
+            })
+        );
+        assert_eq!(
+            command(&["map", "79"]),
+            Ok(Command::Map {
+                from: "seed".to_string(),
+                to: "location".to_string(),
+                values: vec![79]
+            })
+        );
+        assert!(command(&["map"]).is_err());
+        assert!(command(&["map", "--from"]).is_err());
+        assert!(command(&["map", "seventy"]).is_err());
+        assert!(command(&["79"]).is_err());
+        assert!(command(&["map", "--format", "json", "79"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(
+            parse_args(args(&["--mode", "lenient"])),
+            Ok(Options {
+                command: Command::Minimum { top: 1 },
+                mode: ValidationMode::Lenient
+            })
+        );
+        assert_eq!(
+            parse_args(args(&["compose", "--mode=strict"])).map(|options| options.mode),
+            Ok(ValidationMode::Strict)
+        );
+        assert!(parse_args(args(&["--mode", "loose"])).is_err());
+        assert!(parse_args(args(&["--from", "soil"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_inverse() {
+        assert_eq!(
+            command(&["inverse", "46", "50..60"]),
+            Ok(Command::Inverse {
+                from: "seed".to_string(),
+                to: "location".to_string(),
+                ranges: vec![46..47, 50..60]
+            })
+        );
+        assert!(command(&["inverse"]).is_err());
+        assert!(command(&["inverse", "60..50"]).is_err());
+        assert!(command(&["inverse", "5.."]).is_err());
+        assert_eq!(format_ranges(&[46..47, 50..60]), "46, 50..60");
+    }
+
+    #[test]
+    fn test_parse_compose() {
+        assert_eq!(
+            command(&["compose", "--to", "water", "--format=json"]),
+            Ok(Command::Compose {
+                from: "seed".to_string(),
+                to: "water".to_string(),
+                format: Format::Json
+            })
+        );
+        assert!(command(&["compose", "79"]).is_err());
+        assert!(command(&["map", "79..93"]).is_err());
+        assert!(command(&["compose", "--format", "csv"]).is_err());
     }
 }
@@ -1,130 +1,92 @@
-use std::{fs, u64::MAX};
+use adventofcode2023::cli::{load_almanac, Flags};
+use adventofcode2023::day5::report::{best_seeds_in_ranges, SeedPath};
+use adventofcode2023::day5::validate::ValidationMode;
+use std::io::{self, Write};
+use std::{env, process};
+
+const USAGE: &str = "usage: day5p2 [--mode strict|lenient] [--top <count>]";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    mode: ValidationMode,
+    /// How many of the seeds with the lowest locations to report.
+    top: usize,
+}
 
 pub fn main() {
-    let input = fs::read_to_string("./inputs/day5.txt").expect("Failed to read input");
-    let minimum_location = find_minimum_location(&input);
-    println!("Minimum location: {}", minimum_location);
-}
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
 
-#[derive(Debug)]
-struct MapEntry {
-    destination: u64,
-    source: u64,
-    range: u64,
-}
+    let almanac = load_almanac(options.mode);
 
-// TODO -- this is abysmally slow. I think what I can do is order this list and then only run through
-// the "boundary checks" on each one to reduce the iterations down to something reasonable. It might
-// also be useful to order the vectors in each map as well so we can step through each edge case in
-// sequential order
-fn read_seeds<'a, I>(mut lines: I) -> (Vec<u64>, I)
-where
-    I: Iterator<Item = &'a str>,
-{
-    let seed_ranges: Vec<u64> = lines
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(|s| s.parse().unwrap())
-        .collect();
-
-    let mut seeds: Vec<u64> = Vec::new();
-    for (idx, seed_id) in seed_ranges.iter().enumerate().step_by(2) {
-        let last_seed_id = seed_ranges[idx + 1] + seed_id;
-        for val in *seed_id..last_seed_id {
-            seeds.push(val);
+    match best_seeds_in_ranges(&almanac, options.top) {
+        Ok(best) => write_best(&mut io::stdout().lock(), &best).expect("Failed to write output"),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
         }
     }
-
-    // Consume the empty line after as well to make my life easier
-    lines.next();
-
-    (seeds, lines)
 }
 
-fn read_map<'a, I>(mut lines: I) -> (Vec<MapEntry>, I)
+fn parse_args<I>(args: I) -> Result<Options, String>
 where
-    I: Iterator<Item = &'a str>,
+    I: Iterator<Item = String>,
 {
-    let _name = lines.next().unwrap().trim_end_matches(':');
-    let mut map_values: Vec<MapEntry> = Vec::new();
-
-    while let Some(line) = lines.next() {
-        if line.trim().is_empty() {
-            break;
+    let mut flags = Flags::new(args);
+    let mut options = Options {
+        mode: ValidationMode::default(),
+        top: 1,
+    };
+
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--mode" => options.mode = flags.value()?.parse()?,
+            "--top" => {
+                options.top = match flags.value()?.parse() {
+                    Ok(0) | Err(_) => return Err("--top needs a positive count".to_string()),
+                    Ok(top) => top,
+                }
+            }
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
-
-        let entries: Vec<u64> = line
-            .split_whitespace()
-            .map(|s| s.parse().unwrap())
-            .collect();
-
-        map_values.push(MapEntry {
-            destination: entries[0],
-            source: entries[1],
-            range: entries[2],
-        });
     }
 
-    (map_values, lines)
+    Ok(options)
 }
 
-// Not sure if this is idiomatic Rust, but I liked the idea of passing an iterator back to
-// the same function so that I can just keep reading from the same thing until its used up and
-// all the map data is stored in variables for me to reference later ¯\_(ツ)_/¯
-fn find_minimum_location(input: &str) -> u64 {
-    // Remove the blank lines
-    let lines = input.lines();
-
-    // Read in the seeds
-    let (seeds, lines) = read_seeds(lines);
-
-    // Read in the maps
-    // A smarter person might read this in to a Vec<Vec> and make it more readable
-    // to create them and then loop through them. Maybe I'll do that later
-    let (seed_soil_map, lines) = read_map(lines);
-    let (soil_fertilizer_map, lines) = read_map(lines);
-    let (fertilizer_water_map, lines) = read_map(lines);
-    let (water_light_map, lines) = read_map(lines);
-    let (light_temperature_map, lines) = read_map(lines);
-    let (temperature_humidity_map, lines) = read_map(lines);
-    let (humidity_location_map, _) = read_map(lines);
-
-    let mut min_location: u64 = MAX;
-    for seed in seeds {
-        let mut plant = seed;
-        plant = grow_seed(plant, &seed_soil_map);
-        plant = grow_seed(plant, &soil_fertilizer_map);
-        plant = grow_seed(plant, &fertilizer_water_map);
-        plant = grow_seed(plant, &water_light_map);
-        plant = grow_seed(plant, &light_temperature_map);
-        plant = grow_seed(plant, &temperature_humidity_map);
-        plant = grow_seed(plant, &humidity_location_map);
-
-        if plant < min_location {
-            min_location = plant;
-        }
-    }
-
-    min_location
+fn find_minimum_location(best: &[SeedPath]) -> u64 {
+    best.first().map_or(u64::MAX, SeedPath::location)
 }
 
-fn grow_seed(seed: u64, map: &Vec<MapEntry>) -> u64 {
-    let mut sprout = seed;
-    for entry in map {
-        if seed >= entry.source && seed < entry.source + entry.range {
-            sprout = seed - entry.source + entry.destination;
-            break;
+/// The minimum location, then each seed in `best` with its seed range and
+/// its value in every category.
+fn write_best<W: Write>(out: &mut W, best: &[SeedPath]) -> io::Result<()> {
+    writeln!(out, "Minimum location: {}", find_minimum_location(best))?;
+    for (i, path) in best.iter().enumerate() {
+        if let Some(range) = &path.range {
+            write!(
+                out,
+                "#{} seed range {}..{}: ",
+                i + 1,
+                range.start,
+                range.end
+            )?;
         }
+        writeln!(out, "{}", path)?;
     }
-
-    sprout
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
+    use adventofcode2023::day5::Almanac;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -165,6 +127,47 @@ mod tests {
 
     #[test]
     fn test_parse_map() {
-        assert_eq!(find_minimum_location(INPUT), 46);
+        let best = best_seeds_in_ranges(&Almanac::parse(INPUT), 1).unwrap();
+        assert_eq!(find_minimum_location(&best), 46);
+    }
+
+    #[test]
+    fn test_write_best() {
+        let best = best_seeds_in_ranges(&Almanac::parse(INPUT), 2).unwrap();
+        let mut out = Vec::new();
+        write_best(&mut out, &best).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            indoc! {"
+                Minimum location: 46
+                #1 seed range 79..93: seed 82 -> soil 84 -> fertilizer 84 -> water 84 -> light 77 -> temperature 45 -> humidity 46 -> location 46
+                #2 seed range 79..93: seed 83 -> soil 85 -> fertilizer 85 -> water 85 -> light 78 -> temperature 46 -> humidity 47 -> location 47
+            "}
+        );
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args(&[])),
+            Ok(Options {
+                mode: ValidationMode::Strict,
+                top: 1
+            })
+        );
+        assert_eq!(
+            parse_args(args(&["--mode", "lenient", "--top=5"])),
+            Ok(Options {
+                mode: ValidationMode::Lenient,
+                top: 5
+            })
+        );
+        assert_eq!(
+            parse_args(args(&["--mode=strict"])).map(|options| options.mode),
+            Ok(ValidationMode::Strict)
+        );
+        assert!(parse_args(args(&["--mode"])).is_err());
+        assert!(parse_args(args(&["lenient"])).is_err());
+        assert!(parse_args(args(&["--top", "none"])).is_err());
     }
 }
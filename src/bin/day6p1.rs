@@ -1,69 +1,48 @@
-use std::fs;
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day6::physics::BoatModel;
+use adventofcode2023::day6::{read_races, RaceRecord};
+use num::BigUint;
+use std::{env, fs, process};
+
+const USAGE: &str =
+    "usage: day6p1 [--model accel=<a>|speed=<polynomial in h>[,max=<speed>][,penalty=<ms>]]";
 
 pub fn main() {
+    let model = match parse_args(env::args().skip(1)) {
+        Ok(model) => model,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
     let input = fs::read_to_string("./inputs/day6.txt").expect("Failed to read input");
-    let race_data = read_race_data(&input);
-    let error_margin = compute_error_margin(race_data);
+    let race_data = read_races(&input);
+    let error_margin = compute_error_margin(race_data, &model);
     println!("Error margin: {}", error_margin);
 }
 
-#[derive(Debug)]
-struct RaceRecord {
-    time: u32,
-    distance: u32,
-}
+fn parse_args<I>(args: I) -> Result<BoatModel, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut model = BoatModel::default();
 
-impl RaceRecord {
-    fn new(time: u32, distance: u32) -> RaceRecord {
-        RaceRecord { time, distance }
-    }
-
-    fn get_winning_distances(&self) -> Vec<u32> {
-        let mut all_distances: Vec<u32> = Vec::new();
-        for duration in 0..=self.time {
-            let time_left = self.time - duration;
-            let total_distance = duration * time_left;
-            all_distances.push(total_distance);
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--model" => model = flags.value()?.parse()?,
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
-        all_distances
-            .into_iter()
-            .filter(|d| *d > self.distance)
-            .collect()
     }
-}
 
-fn read_race_data(input: &str) -> Vec<RaceRecord> {
-    let lines: Vec<&str> = input.lines().collect();
-    assert!(lines.len() == 2);
-
-    let all_times: Vec<u32> = lines[0]
-        .split_whitespace()
-        .skip(1)
-        .map(|s| s.parse().unwrap())
-        .collect();
-
-    let all_distances: Vec<u32> = lines[1]
-        .split_whitespace()
-        .skip(1)
-        .map(|s| s.parse().unwrap())
-        .collect();
-
-    assert!(all_times.len() == all_distances.len());
-
-    all_times
-        .iter()
-        .enumerate()
-        .map(|(i, time)| {
-            let distance = all_distances[i];
-            RaceRecord::new(*time, distance)
-        })
-        .collect()
+    Ok(model)
 }
 
-fn compute_error_margin(records: Vec<RaceRecord>) -> u32 {
+fn compute_error_margin(records: Vec<RaceRecord>, model: &BoatModel) -> BigUint {
     records
         .into_iter()
-        .map(|record| record.get_winning_distances().len() as u32)
+        .map(|record| model.ways_to_win(&record))
         .reduce(|acc, el| acc * el)
         .unwrap()
 }
@@ -71,6 +50,7 @@ fn compute_error_margin(records: Vec<RaceRecord>) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -79,30 +59,37 @@ mod tests {
     "};
 
     #[test]
-    fn test_read_race_data() {
-        let race_data = read_race_data(INPUT);
-        assert_eq!(race_data.len(), 3);
-        assert_eq!(race_data[0].time, 7);
-        assert_eq!(race_data[0].distance, 9);
-        assert_eq!(race_data[1].time, 15);
-        assert_eq!(race_data[1].distance, 40);
-        assert_eq!(race_data[2].time, 30);
-        assert_eq!(race_data[2].distance, 200);
+    fn test_compute_error_margin() {
+        let race_data = read_races(INPUT);
+        assert_eq!(
+            compute_error_margin(race_data, &BoatModel::default()),
+            BigUint::from(288u32)
+        );
     }
 
     #[test]
-    fn test_travel_distance() {
-        let race_data: RaceRecord = RaceRecord {
-            time: 7,
-            distance: 9,
-        };
-        let distances = vec![0, 6, 10, 12, 12, 10, 6, 0];
-        assert_eq!(race_data.get_winning_distances(), distances);
+    fn test_other_boat() {
+        // Holds 1 to 6, 2 to 13 and 4 to 26 win
+        let model: BoatModel = "accel=2".parse().unwrap();
+        assert_eq!(
+            compute_error_margin(read_races(INPUT), &model),
+            BigUint::from(6u32 * 12 * 23)
+        );
     }
 
     #[test]
-    fn test_compute_error_margin() {
-        let race_data = read_race_data(INPUT);
-        assert_eq!(compute_error_margin(race_data), 288);
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])), Ok(BoatModel::default()));
+        assert_eq!(
+            parse_args(args(&["--model", "accel=2"])),
+            Ok(BoatModel::accelerating(BigUint::from(2u32)))
+        );
+        assert_eq!(
+            parse_args(args(&["--model=speed=h^2,max=9"])).map(|model| model.to_string()),
+            Ok("speed=h^2,max=9".to_string())
+        );
+        assert!(parse_args(args(&["--model"])).is_err());
+        assert!(parse_args(args(&["--model", "warp=9"])).is_err());
+        assert!(parse_args(args(&["accel=2"])).is_err());
     }
 }
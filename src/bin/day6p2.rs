@@ -1,58 +1,57 @@
-use std::fs;
+use adventofcode2023::cli::Flags;
+use adventofcode2023::day6::physics::{count_holds, BoatModel};
+use adventofcode2023::day6::read_race;
+use std::{env, fs, process};
+
+const USAGE: &str =
+    "usage: day6p2 [--model accel=<a>|speed=<polynomial in h>[,max=<speed>][,penalty=<ms>]]";
 
 pub fn main() {
+    let model = match parse_args(env::args().skip(1)) {
+        Ok(model) => model,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
     let input = fs::read_to_string("./inputs/day6.txt").expect("Failed to read input");
-    let race_data = read_race_data(&input);
-    let error_margin = compute_error_margin(race_data);
+    let race_data = read_race(&input);
+    let holds = model.winning_holds(&race_data);
+    let error_margin = count_holds(&holds);
     println!("Error margin: {}", error_margin);
+    if !holds.is_empty() {
+        let holds: Vec<String> = holds
+            .iter()
+            .map(|range| format!("{}..={}", range.start, &range.end - 1u32))
+            .collect();
+        println!("Winning holds: {} ms", holds.join(", "));
+    }
 }
 
-#[derive(Debug)]
-struct RaceRecord {
-    time: u64,
-    distance: u64,
-}
-
-impl RaceRecord {
-    fn new(time: u64, distance: u64) -> RaceRecord {
-        RaceRecord { time, distance }
-    }
+fn parse_args<I>(args: I) -> Result<BoatModel, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut flags = Flags::new(args);
+    let mut model = BoatModel::default();
 
-    fn get_winning_distances(&self) -> Vec<u64> {
-        let mut all_distances: Vec<u64> = Vec::new();
-        for duration in 0..=self.time {
-            let time_left = self.time - duration;
-            let total_distance = duration * time_left;
-            all_distances.push(total_distance);
+    while let Some(flag) = flags.next_flag() {
+        match flag.as_str() {
+            "--model" => model = flags.value()?.parse()?,
+            _ => return Err(format!("unexpected argument {:?}", flag)),
         }
-        all_distances
-            .into_iter()
-            .filter(|d| *d > self.distance)
-            .collect()
     }
-}
-
-fn read_race_data(input: &str) -> RaceRecord {
-    let lines: Vec<&str> = input.lines().collect();
-    assert!(lines.len() == 2);
 
-    let numbers: String = lines[0].split_whitespace().skip(1).collect();
-    let time: u64 = numbers.parse().unwrap();
-
-    let numbers: String = lines[1].split_whitespace().skip(1).collect();
-    let distance = numbers.parse().unwrap();
-
-    RaceRecord::new(time, distance)
-}
-
-fn compute_error_margin(record: RaceRecord) -> u64 {
-    record.get_winning_distances().len() as u64
+    Ok(model)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode2023::cli::args;
     use indoc::indoc;
+    use num::BigUint;
 
     const INPUT: &str = indoc! {"
         Time:      7  15   30
@@ -60,25 +59,19 @@ mod tests {
     "};
 
     #[test]
-    fn test_read_race_data() {
-        let race_data = read_race_data(INPUT);
-        assert_eq!(race_data.time, 71530);
-        assert_eq!(race_data.distance, 940200);
-    }
-
-    #[test]
-    fn test_travel_distance() {
-        let race_data: RaceRecord = RaceRecord {
-            time: 7,
-            distance: 9,
-        };
-        let distances = vec![0, 6, 10, 12, 12, 10, 6, 0];
-        assert_eq!(race_data.get_winning_distances(), distances);
+    fn test_compute_error_margin() {
+        let holds = BoatModel::default().winning_holds(&read_race(INPUT));
+        assert_eq!(count_holds(&holds), BigUint::from(71503u32));
     }
 
     #[test]
-    fn test_compute_error_margin() {
-        // let race_data = read_race_data(INPUT);
-        // assert_eq!(compute_error_margin(race_data), 288);
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])), Ok(BoatModel::default()));
+        assert_eq!(
+            parse_args(args(&["--model=penalty=5"])).map(|model| model.to_string()),
+            Ok("speed=h,penalty=5".to_string())
+        );
+        assert!(parse_args(args(&["--model", "speed=h^"])).is_err());
+        assert!(parse_args(args(&["--top", "5"])).is_err());
     }
 }
@@ -0,0 +1,151 @@
+//! Command-line plumbing shared by the binaries. Each binary matches the
+//! flags it knows; a flag's value is written `--flag value` or `--flag=value`.
+
+use crate::day2::{parse_games_with, Game, ParseOptions};
+use crate::day5::validate::{validate, ValidationMode};
+use crate::day5::Almanac;
+use std::iter::Peekable;
+use std::{fs, process};
+
+/// The arguments after the program name, read one flag at a time.
+pub struct Flags<I: Iterator<Item = String>> {
+    args: Peekable<I>,
+    /// The flag just read, and what followed its `=`, if anything.
+    flag: String,
+    inline: Option<String>,
+}
+
+impl<I: Iterator<Item = String>> Flags<I> {
+    pub fn new(args: I) -> Flags<I> {
+        Flags {
+            args: args.peekable(),
+            flag: String::new(),
+            inline: None,
+        }
+    }
+
+    /// Takes the first argument if it is one of `commands`.
+    pub fn command(&mut self, commands: &[&str]) -> Option<String> {
+        match self.args.peek() {
+            Some(arg) if commands.contains(&arg.as_str()) => self.args.next(),
+            _ => None,
+        }
+    }
+
+    /// The next flag, with anything after its `=` kept back for `value`.
+    pub fn next_flag(&mut self) -> Option<String> {
+        let arg = self.args.next()?;
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        self.flag = flag.clone();
+        self.inline = inline;
+        Some(flag)
+    }
+
+    /// The value of the flag just read: what followed its `=`, or else the
+    /// next argument.
+    pub fn value(&mut self) -> Result<String, String> {
+        self.inline
+            .take()
+            .or_else(|| self.args.next())
+            .ok_or(format!("{} needs a value", self.flag))
+    }
+}
+
+/// A comma-separated flag value, trimmed, with empty items dropped.
+pub fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The day 2 games, with any parse warnings printed. Exits if the input
+/// doesn't parse.
+pub fn load_games(options: &ParseOptions) -> Vec<Game> {
+    let input = fs::read_to_string("./inputs/day2.txt").expect("Failed to read input");
+    let (games, report) = match parse_games_with(&input, options) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Failed to parse games: {}", e);
+            process::exit(1);
+        }
+    };
+
+    for warning in &report.warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    games
+}
+
+/// The day 5 almanac, checked in `mode` with any warnings printed. Exits if
+/// it isn't valid.
+pub fn load_almanac(mode: ValidationMode) -> Almanac {
+    let input = fs::read_to_string("./inputs/day5.txt").expect("Failed to read input");
+    let almanac = Almanac::parse(&input);
+
+    match validate(&almanac, mode) {
+        Ok(warnings) => {
+            for warning in &warnings {
+                eprintln!("warning: {}", warning);
+            }
+        }
+        Err(e) => {
+            eprintln!("Invalid almanac: {}", e);
+            process::exit(1);
+        }
+    }
+
+    almanac
+}
+
+/// `args` the way `env::args().skip(1)` hands them over, for tests.
+pub fn args(args: &[&str]) -> impl Iterator<Item = String> {
+    args.iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        let mut flags = Flags::new(args(&["--mode", "strict", "--top=5", "--empty="]));
+        assert_eq!(flags.next_flag().as_deref(), Some("--mode"));
+        assert_eq!(flags.value(), Ok("strict".to_string()));
+        assert_eq!(flags.next_flag().as_deref(), Some("--top"));
+        assert_eq!(flags.value(), Ok("5".to_string()));
+        assert_eq!(flags.next_flag().as_deref(), Some("--empty"));
+        assert_eq!(flags.value(), Ok(String::new()));
+        assert_eq!(flags.next_flag(), None);
+    }
+
+    #[test]
+    fn test_missing_value() {
+        let mut flags = Flags::new(args(&["--mode"]));
+        flags.next_flag();
+        assert_eq!(flags.value(), Err("--mode needs a value".to_string()));
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(list(" red, ,blue,"), vec!["red", "blue"]);
+        assert!(list("").is_empty());
+    }
+
+    #[test]
+    fn test_command() {
+        let mut flags = Flags::new(args(&["graph", "graph"]));
+        assert_eq!(flags.command(&["sum", "graph"]).as_deref(), Some("graph"));
+        assert_eq!(flags.command(&["sum"]), None);
+        assert_eq!(flags.next_flag().as_deref(), Some("graph"));
+    }
+}
@@ -0,0 +1,456 @@
+mod parse;
+pub mod probability;
+
+pub use parse::{
+    parse_games, parse_games_with, ParseErrorKind, ParseGameError, ParseMode, ParseOptions,
+    ParseReport,
+};
+
+use crate::export::json_string;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// A single handful of cubes pulled out of the bag, e.g. `3 blue, 4 red`.
+///
+/// Colours are kept in the order they were written so that the `Display`
+/// output matches the puzzle input.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Draw {
+    cubes: Vec<(String, u32)>,
+}
+
+impl Draw {
+    pub fn new(cubes: Vec<(String, u32)>) -> Draw {
+        Draw { cubes }
+    }
+
+    /// Number of cubes of `color` in this draw, or 0 if it wasn't drawn.
+    pub fn count(&self, color: &str) -> u32 {
+        self.cubes
+            .iter()
+            .filter(|(c, _)| c == color)
+            .map(|(_, n)| *n)
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.cubes.iter().map(|(c, n)| (c.as_str(), *n))
+    }
+}
+
+impl fmt::Display for Draw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (color, number)) in self.cubes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", number, color)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of the puzzle input: `Game <id>: <draw>; <draw>; ...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: u32,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    /// The largest number of cubes of `color` seen in any single draw.
+    pub fn max_count(&self, color: &str) -> u32 {
+        self.draws
+            .iter()
+            .map(|draw| draw.count(color))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The smallest bag that could have produced every draw in this game.
+    pub fn minimum_bag(&self) -> Bag {
+        minimum_bag(std::slice::from_ref(self))
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}:", self.id)?;
+        for (i, draw) in self.draws.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, " {}", draw)?;
+        }
+        Ok(())
+    }
+}
+
+/// The cubes loaded into the bag, e.g. `red=12,green=13,blue=14`.
+///
+/// Any colour that isn't listed is treated as having no cubes in the bag.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Bag {
+    cubes: BTreeMap<String, u32>,
+}
+
+impl Bag {
+    pub fn new(cubes: BTreeMap<String, u32>) -> Bag {
+        Bag { cubes }
+    }
+
+    pub fn count(&self, color: &str) -> u32 {
+        self.cubes.get(color).copied().unwrap_or(0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.cubes.iter().map(|(c, n)| (c.as_str(), *n))
+    }
+
+    /// Every roll in `game` that asks for more cubes than the bag holds.
+    pub fn violations(&self, game: &Game) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (draw_index, draw) in game.draws.iter().enumerate() {
+            let mut seen: Vec<&str> = Vec::new();
+            for (color, _) in draw.iter() {
+                if seen.contains(&color) {
+                    continue;
+                }
+                seen.push(color);
+
+                let count = draw.count(color);
+                let limit = self.count(color);
+                if count > limit {
+                    violations.push(Violation {
+                        game_id: game.id,
+                        draw_index,
+                        color: color.to_string(),
+                        count,
+                        limit,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    pub fn is_possible(&self, game: &Game) -> bool {
+        self.violations(game).is_empty()
+    }
+
+    /// Product of the cube counts of every colour in the bag.
+    pub fn power(&self) -> u64 {
+        self.cubes.values().map(|n| *n as u64).product()
+    }
+
+    /// Product of the cube counts of `colors` only. A colour missing from the
+    /// bag contributes 0.
+    pub fn power_of(&self, colors: &[&str]) -> u64 {
+        colors.iter().map(|c| self.count(c) as u64).product()
+    }
+}
+
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (color, number)) in self.cubes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", color, number)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bag {
+    type Err = ParseBagError;
+
+    fn from_str(input: &str) -> Result<Bag, ParseBagError> {
+        let mut cubes = BTreeMap::new();
+        for entry in input.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (color, number) = entry
+                .split_once('=')
+                .ok_or_else(|| ParseBagError::InvalidEntry(entry.trim().to_string()))?;
+            let color = color.trim();
+            let number = number
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| ParseBagError::InvalidEntry(entry.trim().to_string()))?;
+
+            if color.is_empty() {
+                return Err(ParseBagError::InvalidEntry(entry.trim().to_string()));
+            }
+            if cubes.insert(color.to_string(), number).is_some() {
+                return Err(ParseBagError::DuplicateColor(color.to_string()));
+            }
+        }
+
+        Ok(Bag::new(cubes))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBagError {
+    InvalidEntry(String),
+    DuplicateColor(String),
+}
+
+impl fmt::Display for ParseBagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBagError::InvalidEntry(entry) => {
+                write!(f, "expected `<color>=<count>`, got {:?}", entry)
+            }
+            ParseBagError::DuplicateColor(color) => write!(f, "{} listed more than once", color),
+        }
+    }
+}
+
+impl Error for ParseBagError {}
+
+/// A draw that pulled more cubes of one colour than the bag holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub game_id: u32,
+    /// Zero-based index of the offending draw within the game.
+    pub draw_index: usize,
+    pub color: String,
+    pub count: u32,
+    pub limit: u32,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Game {}, draw {}: {} {} but the bag only holds {}",
+            self.game_id,
+            self.draw_index + 1,
+            self.count,
+            self.color,
+            self.limit
+        )
+    }
+}
+
+/// The smallest bag that could have produced every draw of every game given,
+/// i.e. the per-colour maximum over the union of their draws.
+pub fn minimum_bag<'a, I>(games: I) -> Bag
+where
+    I: IntoIterator<Item = &'a Game>,
+{
+    let mut cubes: BTreeMap<String, u32> = BTreeMap::new();
+    for draw in games.into_iter().flat_map(|game| &game.draws) {
+        for (color, _) in draw.iter() {
+            let count = draw.count(color);
+            let max = cubes.entry(color.to_string()).or_insert(0);
+            if count > *max {
+                *max = count;
+            }
+        }
+    }
+
+    Bag::new(cubes)
+}
+
+/// Write one CSV row per game with its minimum bag restricted to `colors`
+/// and the power over those colours.
+pub fn write_minimum_bags_csv<W: Write>(
+    out: &mut W,
+    games: &[Game],
+    colors: &[&str],
+) -> io::Result<()> {
+    write!(out, "game")?;
+    for color in colors {
+        write!(out, ",{}", csv_field(color))?;
+    }
+    writeln!(out, ",power")?;
+
+    for game in games {
+        let bag = game.minimum_bag();
+        write!(out, "{}", game.id)?;
+        for color in colors {
+            write!(out, ",{}", bag.count(color))?;
+        }
+        writeln!(out, ",{}", bag.power_of(colors))?;
+    }
+
+    Ok(())
+}
+
+/// Same as `write_minimum_bags_csv`, as a JSON array of
+/// `{"game": .., "bag": {..}, "power": ..}` objects.
+pub fn write_minimum_bags_json<W: Write>(
+    out: &mut W,
+    games: &[Game],
+    colors: &[&str],
+) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, game) in games.iter().enumerate() {
+        let bag = game.minimum_bag();
+        let counts: Vec<String> = colors
+            .iter()
+            .map(|color| format!("{}: {}", json_string(color), bag.count(color)))
+            .collect();
+
+        write!(
+            out,
+            "  {{\"game\": {}, \"bag\": {{{}}}, \"power\": {}}}",
+            game.id,
+            counts.join(", "),
+            bag.power_of(colors)
+        )?;
+        writeln!(out, "{}", if i + 1 < games.len() { "," } else { "" })?;
+    }
+    writeln!(out, "]")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+        Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+        Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+    "};
+
+    #[test]
+    fn test_parse_game() {
+        let game: Game = "Game 3: 8 green, 6 blue, 20 red; 5 green, 1 red"
+            .parse()
+            .unwrap();
+        assert_eq!(game.id, 3);
+        assert_eq!(game.draws.len(), 2);
+        assert_eq!(game.draws[0].count("red"), 20);
+        assert_eq!(game.draws[1].count("blue"), 0);
+        assert_eq!(game.max_count("green"), 8);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let games = parse_games(INPUT).unwrap();
+        assert_eq!(games.len(), 5);
+
+        let printed: Vec<String> = games.iter().map(|game| game.to_string()).collect();
+        assert_eq!(printed, INPUT.lines().collect::<Vec<&str>>());
+    }
+
+    #[test]
+    fn test_parse_bag() {
+        let bag: Bag = "red=12, green=13,blue=14,yellow=3".parse().unwrap();
+        assert_eq!(bag.count("red"), 12);
+        assert_eq!(bag.count("yellow"), 3);
+        assert_eq!(bag.count("purple"), 0);
+        assert_eq!(bag.to_string(), "blue=14,green=13,red=12,yellow=3");
+
+        assert_eq!(
+            "red=12,red=3".parse::<Bag>(),
+            Err(ParseBagError::DuplicateColor("red".to_string()))
+        );
+        assert_eq!(
+            "red:12".parse::<Bag>(),
+            Err(ParseBagError::InvalidEntry("red:12".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let bag: Bag = "red=12,green=13,blue=14".parse().unwrap();
+        let games = parse_games(INPUT).unwrap();
+
+        let possible: Vec<u32> = games
+            .iter()
+            .filter(|game| bag.is_possible(game))
+            .map(|game| game.id)
+            .collect();
+        assert_eq!(possible, vec![1, 2, 5]);
+
+        let violations = bag.violations(&games[3]);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].draw_index, 2);
+        assert_eq!(violations[0].color, "blue");
+        assert_eq!(violations[1].color, "red");
+        assert_eq!(violations[1].count, 14);
+        assert_eq!(violations[1].limit, 12);
+    }
+
+    #[test]
+    fn test_violations_unknown_color() {
+        let bag: Bag = "red=12,green=13,blue=14".parse().unwrap();
+        let game: Game = "Game 7: 1 red; 2 yellow".parse().unwrap();
+
+        let violations = bag.violations(&game);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].to_string(),
+            "Game 7, draw 2: 2 yellow but the bag only holds 0"
+        );
+    }
+
+    #[test]
+    fn test_minimum_bag() {
+        let games = parse_games(INPUT).unwrap();
+
+        let bag = games[0].minimum_bag();
+        assert_eq!(bag.to_string(), "blue=6,green=2,red=4");
+        assert_eq!(bag.power(), 48);
+        assert_eq!(bag.power_of(&["red", "blue"]), 24);
+        assert_eq!(bag.power_of(&["red", "yellow"]), 0);
+
+        let bag = minimum_bag(&[games[0].clone(), games[2].clone()]);
+        assert_eq!(bag.to_string(), "blue=6,green=13,red=20");
+
+        let game: Game = "Game 9: 2 yellow, 1 red; 4 yellow".parse().unwrap();
+        assert_eq!(game.minimum_bag().to_string(), "red=1,yellow=4");
+    }
+
+    #[test]
+    fn test_write_minimum_bags() {
+        let games = parse_games(INPUT).unwrap();
+        let colors = ["red", "green", "blue"];
+
+        let mut csv = Vec::new();
+        write_minimum_bags_csv(&mut csv, &games[..2], &colors).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            indoc! {"
+                game,red,green,blue,power
+                1,4,2,6,48
+                2,1,3,4,12
+            "}
+        );
+
+        let mut json = Vec::new();
+        write_minimum_bags_json(&mut json, &games[..2], &colors).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            indoc! {r#"
+                [
+                  {"game": 1, "bag": {"red": 4, "green": 2, "blue": 6}, "power": 48},
+                  {"game": 2, "bag": {"red": 1, "green": 3, "blue": 4}, "power": 12}
+                ]
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("red"), "red");
+    }
+}
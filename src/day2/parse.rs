@@ -0,0 +1,380 @@
+//! Parsing of `Game <id>: <draw>; <draw>; ...` records.
+//!
+//! In `Strict` mode the first problem aborts parsing with an error pointing at
+//! the offending line and column. In `Lenient` mode the same problems are
+//! collected into a `ParseReport` and parsing carries on: malformed rolls are
+//! dropped, games with an unreadable header are skipped, and unknown or
+//! repeated colours are kept as written.
+
+use super::{Draw, Game};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+impl FromStr for ParseMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<ParseMode, String> {
+        match input {
+            "strict" => Ok(ParseMode::Strict),
+            "lenient" => Ok(ParseMode::Lenient),
+            _ => Err(format!("unknown parse mode {:?}", input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    /// Colours allowed in a roll. `None` accepts any colour.
+    pub known_colors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingHeader,
+    InvalidId(String),
+    InvalidRoll(String),
+    UnknownColor(String),
+    DuplicateColor(String),
+}
+
+/// A problem found while parsing, located by 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseGameError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseGameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::MissingHeader => write!(f, "missing `Game <id>:`"),
+            ParseErrorKind::InvalidId(header) => write!(f, "invalid game id in {:?}", header),
+            ParseErrorKind::InvalidRoll(roll) => write!(f, "invalid roll {:?}", roll),
+            ParseErrorKind::UnknownColor(color) => write!(f, "unknown color {:?}", color),
+            ParseErrorKind::DuplicateColor(color) => {
+                write!(f, "{} appears more than once in one draw", color)
+            }
+        }
+    }
+}
+
+impl Error for ParseGameError {}
+
+/// Warnings collected while parsing in `Lenient` mode.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParseReport {
+    pub warnings: Vec<ParseGameError>,
+}
+
+impl ParseReport {
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Parse every non-empty line of the input into a `Game`, failing on the first
+/// malformed record.
+pub fn parse_games(input: &str) -> Result<Vec<Game>, ParseGameError> {
+    parse_games_with(input, &ParseOptions::default()).map(|(games, _)| games)
+}
+
+/// Parse every non-empty line of the input according to `options`.
+pub fn parse_games_with(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<Game>, ParseReport), ParseGameError> {
+    let mut report = ParseReport::default();
+    let mut games = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut parser = LineParser {
+            options,
+            line_number: i + 1,
+            line,
+            report: &mut report,
+        };
+        if let Some(game) = parser.game()? {
+            games.push(game);
+        }
+    }
+
+    Ok((games, report))
+}
+
+impl FromStr for Game {
+    type Err = ParseGameError;
+
+    fn from_str(line: &str) -> Result<Game, ParseGameError> {
+        let mut report = ParseReport::default();
+        let mut parser = LineParser {
+            options: &ParseOptions::default(),
+            line_number: 1,
+            line,
+            report: &mut report,
+        };
+
+        // Strict mode never skips a game, it errors instead
+        Ok(parser.game()?.expect("strict parsing skipped a game"))
+    }
+}
+
+impl FromStr for Draw {
+    type Err = ParseGameError;
+
+    fn from_str(input: &str) -> Result<Draw, ParseGameError> {
+        let mut report = ParseReport::default();
+        let mut parser = LineParser {
+            options: &ParseOptions::default(),
+            line_number: 1,
+            line: input,
+            report: &mut report,
+        };
+
+        parser.draw(input)
+    }
+}
+
+struct LineParser<'a> {
+    options: &'a ParseOptions,
+    line_number: usize,
+    line: &'a str,
+    report: &'a mut ParseReport,
+}
+
+impl<'a> LineParser<'a> {
+    /// Parse the whole line. Returns `None` when a lenient parse had to skip it.
+    fn game(&mut self) -> Result<Option<Game>, ParseGameError> {
+        let line = self.line;
+        let Some((header, draws)) = line.split_once(':') else {
+            self.problem(line, ParseErrorKind::MissingHeader)?;
+            return Ok(None);
+        };
+
+        let Some(id) = parse_game_id(header) else {
+            self.problem(header, ParseErrorKind::InvalidId(header.trim().to_string()))?;
+            return Ok(None);
+        };
+
+        let draws = draws
+            .split(';')
+            .map(|draw| self.draw(draw))
+            .collect::<Result<Vec<Draw>, _>>()?;
+
+        Ok(Some(Game { id, draws }))
+    }
+
+    fn draw(&mut self, input: &'a str) -> Result<Draw, ParseGameError> {
+        let mut cubes: Vec<(String, u32)> = Vec::new();
+
+        for roll in input.split(',') {
+            let Some((number, color)) = parse_number_and_color(roll) else {
+                self.problem(roll, ParseErrorKind::InvalidRoll(roll.trim().to_string()))?;
+                continue;
+            };
+
+            if let Some(known) = &self.options.known_colors {
+                if !known.iter().any(|c| c == color) {
+                    self.problem(roll, ParseErrorKind::UnknownColor(color.to_string()))?;
+                }
+            }
+            if cubes.iter().any(|(c, _)| c == color) {
+                self.problem(roll, ParseErrorKind::DuplicateColor(color.to_string()))?;
+            }
+
+            cubes.push((color.to_string(), number));
+        }
+
+        Ok(Draw::new(cubes))
+    }
+
+    /// Fail in strict mode, or note the problem and carry on in lenient mode.
+    /// `at` must be a slice of the line being parsed.
+    fn problem(&mut self, at: &str, kind: ParseErrorKind) -> Result<(), ParseGameError> {
+        let error = ParseGameError {
+            line: self.line_number,
+            column: self.column_of(at),
+            kind,
+        };
+
+        match self.options.mode {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                self.report.warnings.push(error);
+                Ok(())
+            }
+        }
+    }
+
+    fn column_of(&self, at: &str) -> usize {
+        let at = at.trim_start();
+        let offset = (at.as_ptr() as usize)
+            .saturating_sub(self.line.as_ptr() as usize)
+            .min(self.line.len());
+        self.line[..offset].chars().count() + 1
+    }
+}
+
+fn parse_number_and_color(input: &str) -> Option<(u32, &str)> {
+    let mut iter = input.split_whitespace();
+
+    let number = iter.next()?.parse::<u32>().ok()?;
+    let color = iter.next()?;
+    if iter.next().is_some() {
+        return None;
+    }
+
+    Some((number, color))
+}
+
+fn parse_game_id(input: &str) -> Option<u32> {
+    let mut iter = input.split_whitespace();
+
+    if iter.next()? != "Game" {
+        return None;
+    }
+    let id = iter.next()?.parse::<u32>().ok()?;
+    if iter.next().is_some() {
+        return None;
+    }
+
+    Some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const MALFORMED: &str = indoc! {"
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Gaem 2: 1 blue, 2 green
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, four red
+        Game 4: 1 green, 3 red, 6 blue; 3 green, 2 purple
+        Game 5: 6 red, 1 blue, 3 green, 2 red
+    "};
+
+    fn error(line: usize, column: usize, kind: ParseErrorKind) -> ParseGameError {
+        ParseGameError { line, column, kind }
+    }
+
+    fn rgb() -> Option<Vec<String>> {
+        Some(vec![
+            "red".to_string(),
+            "green".to_string(),
+            "blue".to_string(),
+        ])
+    }
+
+    #[test]
+    fn test_parse_number_and_color() {
+        assert_eq!(parse_number_and_color("3 blue"), Some((3, "blue")));
+        assert_eq!(parse_number_and_color(" 13 green"), Some((13, "green")));
+        assert_eq!(parse_number_and_color("red 1"), None);
+        assert_eq!(parse_number_and_color("1 dark red"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "3 blue".parse::<Game>(),
+            Err(error(1, 1, ParseErrorKind::MissingHeader))
+        );
+        assert_eq!(
+            "Game x: 3 blue".parse::<Game>(),
+            Err(error(1, 1, ParseErrorKind::InvalidId("Game x".to_string())))
+        );
+        assert_eq!(
+            "Game 1: 3 blue; four red".parse::<Game>(),
+            Err(error(
+                1,
+                17,
+                ParseErrorKind::InvalidRoll("four red".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let options = ParseOptions {
+            mode: ParseMode::Strict,
+            known_colors: rgb(),
+        };
+
+        let err = parse_games_with(MALFORMED, &options).unwrap_err();
+        assert_eq!(
+            err,
+            error(2, 1, ParseErrorKind::InvalidId("Gaem 2".to_string()))
+        );
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: invalid game id in \"Gaem 2\""
+        );
+
+        let input = "Game 4: 1 green, 3 red, 6 blue; 3 green, 2 purple";
+        assert_eq!(
+            parse_games_with(input, &options).unwrap_err(),
+            error(1, 42, ParseErrorKind::UnknownColor("purple".to_string()))
+        );
+
+        let input = "Game 5: 6 red, 1 blue, 3 green, 2 red";
+        assert_eq!(
+            parse_games_with(input, &options).unwrap_err(),
+            error(1, 33, ParseErrorKind::DuplicateColor("red".to_string()))
+        );
+
+        // Without a colour list any colour is fine
+        let input = "Game 4: 1 green, 3 red, 6 blue; 3 green, 2 purple";
+        assert!(parse_games_with(input, &ParseOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_lenient() {
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            known_colors: rgb(),
+        };
+
+        let (games, report) = parse_games_with(MALFORMED, &options).unwrap();
+        let ids: Vec<u32> = games.iter().map(|game| game.id).collect();
+        assert_eq!(ids, vec![1, 3, 4, 5]);
+
+        // The malformed roll is dropped, the rest of the draw kept
+        assert_eq!(games[1].draws[1].to_string(), "5 blue");
+        // Unknown and repeated colours are kept as written
+        assert_eq!(games[2].draws[1].count("purple"), 2);
+        assert_eq!(games[3].draws[0].count("red"), 8);
+
+        assert_eq!(
+            report.warnings,
+            vec![
+                error(2, 1, ParseErrorKind::InvalidId("Gaem 2".to_string())),
+                error(3, 42, ParseErrorKind::InvalidRoll("four red".to_string())),
+                error(4, 42, ParseErrorKind::UnknownColor("purple".to_string())),
+                error(5, 33, ParseErrorKind::DuplicateColor("red".to_string())),
+            ]
+        );
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_parse_mode_from_str() {
+        assert_eq!("strict".parse(), Ok(ParseMode::Strict));
+        assert_eq!("lenient".parse(), Ok(ParseMode::Lenient));
+        assert!("loose".parse::<ParseMode>().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Likelihood of the observed games under a given bag.
+//!
+//! Each draw is modelled as a uniform sample, without replacement, of as many
+//! cubes as were shown, after which the cubes go back in the bag. That makes
+//! every draw an independent multivariate hypergeometric sample:
+//!
+//! ```text
+//! P(draw) = prod_c C(K_c, k_c) / C(N, n)
+//! ```
+//!
+//! where `K_c` is the number of cubes of colour `c` in the bag, `k_c` the number
+//! drawn, `N` the size of the bag and `n` the size of the draw.
+//!
+//! Probabilities are computed exactly as `BigRational`s. The `ln_*` variants
+//! work in log-space with `f64`, which is handy for printing and for games
+//! whose exact probability has an enormous denominator.
+
+use super::{Bag, Draw, Game};
+use num::integer::binomial;
+use num::{BigInt, BigRational, One, Zero};
+
+/// Exact probability of seeing `draw` when sampling from `bag`.
+pub fn draw_probability(bag: &Bag, draw: &Draw) -> BigRational {
+    let total: u64 = bag.iter().map(|(_, n)| n as u64).sum();
+    let drawn: u64 = draw.iter().map(|(_, n)| n as u64).sum();
+
+    let mut favourable = BigInt::one();
+    for (color, count) in distinct_counts(draw) {
+        favourable *= binomial(BigInt::from(bag.count(color)), BigInt::from(count));
+    }
+    if favourable.is_zero() {
+        return BigRational::zero();
+    }
+
+    let possible = binomial(BigInt::from(total), BigInt::from(drawn));
+    BigRational::new(favourable, possible)
+}
+
+/// Exact probability of seeing every draw in `game`, in order.
+pub fn game_probability(bag: &Bag, game: &Game) -> BigRational {
+    game.draws
+        .iter()
+        .map(|draw| draw_probability(bag, draw))
+        .fold(BigRational::one(), |acc, p| acc * p)
+}
+
+/// Natural log of `draw_probability`, or negative infinity if the draw is impossible.
+pub fn ln_draw_probability(bag: &Bag, draw: &Draw) -> f64 {
+    let total: u64 = bag.iter().map(|(_, n)| n as u64).sum();
+    let drawn: u64 = draw.iter().map(|(_, n)| n as u64).sum();
+
+    let ln_favourable: f64 = distinct_counts(draw)
+        .iter()
+        .map(|(color, count)| ln_binomial(bag.count(color) as u64, *count as u64))
+        .sum();
+    if ln_favourable == f64::NEG_INFINITY {
+        return f64::NEG_INFINITY;
+    }
+
+    ln_favourable - ln_binomial(total, drawn)
+}
+
+/// Natural log of `game_probability`.
+pub fn ln_game_probability(bag: &Bag, game: &Game) -> f64 {
+    game.draws
+        .iter()
+        .map(|draw| ln_draw_probability(bag, draw))
+        .sum()
+}
+
+/// How likely a set of games is under one bag hypothesis.
+#[derive(Debug, Clone)]
+pub struct Ranking<'a> {
+    pub bag: &'a Bag,
+    pub likelihood: BigRational,
+    pub ln_likelihood: f64,
+}
+
+/// Order the `bags` from most to least likely to have produced all of `games`.
+/// Ties keep the order the bags were given in.
+pub fn rank_bags<'a>(bags: &'a [Bag], games: &[Game]) -> Vec<Ranking<'a>> {
+    let mut rankings: Vec<Ranking> = bags
+        .iter()
+        .map(|bag| Ranking {
+            bag,
+            likelihood: games
+                .iter()
+                .map(|game| game_probability(bag, game))
+                .fold(BigRational::one(), |acc, p| acc * p),
+            ln_likelihood: games
+                .iter()
+                .map(|game| ln_game_probability(bag, game))
+                .sum(),
+        })
+        .collect();
+
+    rankings.sort_by(|a, b| b.likelihood.cmp(&a.likelihood));
+    rankings
+}
+
+/// Count per colour, merging repeated colours within the draw.
+fn distinct_counts(draw: &Draw) -> Vec<(&str, u32)> {
+    let mut counts: Vec<(&str, u32)> = Vec::new();
+    for (color, _) in draw.iter() {
+        if !counts.iter().any(|(c, _)| *c == color) {
+            counts.push((color, draw.count(color)));
+        }
+    }
+    counts
+}
+
+fn ln_binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+
+    let k = k.min(n - k);
+    (1..=k)
+        .map(|i| ((n - k + i) as f64).ln() - (i as f64).ln())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::parse_games;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+    "};
+
+    fn ratio(numer: i64, denom: i64) -> BigRational {
+        BigRational::new(BigInt::from(numer), BigInt::from(denom))
+    }
+
+    #[test]
+    fn test_draw_probability() {
+        let bag: Bag = "red=2,blue=1".parse().unwrap();
+
+        // {r1, r2, b}: pairs are r1r2, r1b, r2b
+        let draw: Draw = "1 red, 1 blue".parse().unwrap();
+        assert_eq!(draw_probability(&bag, &draw), ratio(2, 3));
+
+        let draw: Draw = "2 red".parse().unwrap();
+        assert_eq!(draw_probability(&bag, &draw), ratio(1, 3));
+
+        let draw: Draw = "2 blue".parse().unwrap();
+        assert_eq!(draw_probability(&bag, &draw), ratio(0, 1));
+
+        let draw: Draw = "1 green".parse().unwrap();
+        assert_eq!(draw_probability(&bag, &draw), ratio(0, 1));
+        assert_eq!(ln_draw_probability(&bag, &draw), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_repeated_color_in_draw() {
+        let bag: Bag = "red=2,blue=1".parse().unwrap();
+        let split = Draw::new(vec![("red".to_string(), 1), ("red".to_string(), 1)]);
+        let merged: Draw = "2 red".parse().unwrap();
+        assert_eq!(
+            draw_probability(&bag, &split),
+            draw_probability(&bag, &merged)
+        );
+    }
+
+    #[test]
+    fn test_game_probability() {
+        let bag: Bag = "red=2,blue=1".parse().unwrap();
+        let game: Game = "Game 1: 1 red, 1 blue; 2 red; 1 blue".parse().unwrap();
+
+        let p = game_probability(&bag, &game);
+        assert_eq!(p, ratio(2, 27));
+        assert!((ln_game_probability(&bag, &game) - (2.0f64 / 27.0).ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ln_matches_exact() {
+        let bag: Bag = "red=12,green=13,blue=14".parse().unwrap();
+        let games = parse_games(INPUT).unwrap();
+
+        for game in &games[..2] {
+            let exact = game_probability(&bag, game);
+            let exact = num::ToPrimitive::to_f64(&exact).unwrap().ln();
+            assert!((ln_game_probability(&bag, game) - exact).abs() < 1e-9);
+        }
+        assert!(game_probability(&bag, &games[2]).is_zero());
+    }
+
+    #[test]
+    fn test_rank_bags() {
+        let bags: Vec<Bag> = ["red=12,green=13,blue=14", "red=20,green=13,blue=6", "red=1"]
+            .iter()
+            .map(|bag| bag.parse().unwrap())
+            .collect();
+        let games = parse_games(INPUT).unwrap();
+
+        let rankings = rank_bags(&bags, &games);
+        assert_eq!(rankings[0].bag, &bags[1]);
+        assert!(rankings[0].likelihood > BigRational::zero());
+        assert!(rankings[1].likelihood.is_zero());
+        assert!(rankings[2].likelihood.is_zero());
+        assert_eq!(rankings[1].ln_likelihood, f64::NEG_INFINITY);
+
+        let rankings = rank_bags(&bags, &games[..2]);
+        assert!(rankings[0].likelihood >= rankings[1].likelihood);
+        assert!(rankings[0].ln_likelihood >= rankings[1].ln_likelihood);
+        assert_eq!(rankings[2].bag, &bags[2]);
+    }
+}
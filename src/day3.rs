@@ -0,0 +1,549 @@
+pub mod graph;
+pub mod render;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A run of digits in the schematic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartInfo {
+    pub column_index: usize,
+    pub row_index: usize,
+    pub length: usize,
+    pub value: String,
+}
+
+impl PartInfo {
+    pub fn number(&self) -> Result<u64, NumberError> {
+        self.value.parse().map_err(|_| NumberError::PartNumber {
+            row: self.row_index,
+            column: self.column_index,
+        })
+    }
+}
+
+/// A number too big for a `u64`. Rows and columns count from 0, as in the
+/// graph export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    /// The part number starting at this cell has too many digits.
+    PartNumber { row: usize, column: usize },
+    /// The ratio of the gear at this cell.
+    GearRatio { row: usize, column: usize },
+    /// The sum of the part numbers or gear ratios.
+    Sum,
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberError::PartNumber { row, column } => {
+                write!(
+                    f,
+                    "part number at ({}, {}) doesn't fit in a u64",
+                    row, column
+                )
+            }
+            NumberError::GearRatio { row, column } => {
+                write!(
+                    f,
+                    "ratio of the gear at ({}, {}) doesn't fit in a u64",
+                    row, column
+                )
+            }
+            NumberError::Sum => write!(f, "sum doesn't fit in a u64"),
+        }
+    }
+}
+
+impl Error for NumberError {}
+
+/// The engine schematic along with an index from every cell to the part
+/// number covering it, so adjacency lookups don't have to scan every part.
+///
+/// Rows don't need to be the same length; cells past the end of a row simply
+/// don't exist.
+pub struct Schematic {
+    rows: Vec<Vec<char>>,
+    parts: Vec<PartInfo>,
+    part_index: Vec<Vec<Option<usize>>>,
+}
+
+impl Schematic {
+    pub fn new(schematic: &str) -> Schematic {
+        let rows: Vec<Vec<char>> = schematic
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        let parts = read_data(schematic);
+
+        let mut part_index: Vec<Vec<Option<usize>>> =
+            rows.iter().map(|row| vec![None; row.len()]).collect();
+        for (id, part) in parts.iter().enumerate() {
+            let columns = part.column_index..part.column_index + part.length;
+            for cell in &mut part_index[part.row_index][columns] {
+                *cell = Some(id);
+            }
+        }
+
+        Schematic {
+            rows,
+            parts,
+            part_index,
+        }
+    }
+
+    pub fn rows(&self) -> &[Vec<char>] {
+        &self.rows
+    }
+
+    pub fn parts(&self) -> &[PartInfo] {
+        &self.parts
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<char> {
+        self.rows.get(row)?.get(column).copied()
+    }
+
+    /// Id (index into `parts`) of the part number covering this cell.
+    pub fn part_at(&self, row: usize, column: usize) -> Option<usize> {
+        *self.part_index.get(row)?.get(column)?
+    }
+
+    /// Every cell in the schematic, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(column, c)| (row, column, *c))
+        })
+    }
+
+    /// The up to eight cells surrounding `(row, column)` that exist in the schematic.
+    pub fn neighbours(
+        &self,
+        row: usize,
+        column: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rows = row.saturating_sub(1)..=row + 1;
+        rows.flat_map(move |r| {
+            let columns = column.saturating_sub(1)..=column + 1;
+            columns.map(move |c| (r, c))
+        })
+        .filter(move |&(r, c)| (r, c) != (row, column) && self.get(r, c).is_some())
+    }
+
+    /// Ids of the distinct part numbers touching `(row, column)`, in reading order.
+    pub fn adjacent_parts(&self, row: usize, column: usize) -> Vec<usize> {
+        let mut ids: Vec<usize> = Vec::new();
+        for (r, c) in self.neighbours(row, column) {
+            if let Some(id) = self.part_at(r, c) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+
+    /// The cells bordering part `id`, not counting the part's own digits.
+    pub fn part_neighbours(&self, id: usize) -> Vec<(usize, usize)> {
+        let part = &self.parts[id];
+        let mut cells: Vec<(usize, usize)> = Vec::new();
+        for column in part.column_index..part.column_index + part.length {
+            for cell in self.neighbours(part.row_index, column) {
+                if self.part_at(cell.0, cell.1) != Some(id) && !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+        cells
+    }
+
+    /// Whether part `id` touches a symbol, diagonals included.
+    pub fn is_part_number(&self, id: usize, rules: &Rules) -> bool {
+        self.part_neighbours(id).iter().any(|&(row, column)| {
+            self.get(row, column)
+                .is_some_and(|c| rules.symbols.contains(c))
+        })
+    }
+
+    /// Ids of every part number that touches a symbol.
+    pub fn part_numbers(&self, rules: &Rules) -> Vec<usize> {
+        (0..self.parts.len())
+            .filter(|&id| self.is_part_number(id, rules))
+            .collect()
+    }
+
+    pub fn sum_part_numbers(&self, rules: &Rules) -> Result<u64, NumberError> {
+        self.part_numbers(rules).iter().try_fold(0u64, |sum, &id| {
+            sum.checked_add(self.parts[id].number()?)
+                .ok_or(NumberError::Sum)
+        })
+    }
+
+    /// Every gear symbol with the right number of adjacent parts, in reading order.
+    pub fn gears(&self, rules: &Rules) -> Vec<Gear> {
+        self.cells()
+            .filter(|&(_, _, c)| rules.gears.contains(&c))
+            .filter_map(|(row, column, symbol)| {
+                let parts = self.adjacent_parts(row, column);
+                if !rules.arity.accepts(parts.len()) {
+                    return None;
+                }
+
+                let ratio = parts
+                    .iter()
+                    .map(|&id| self.parts[id].number())
+                    .collect::<Result<Vec<u64>, _>>()
+                    .and_then(|values| {
+                        rules
+                            .ratio
+                            .apply(values.into_iter())
+                            .ok_or(NumberError::GearRatio { row, column })
+                    });
+                Some(Gear {
+                    row,
+                    column,
+                    symbol,
+                    ratio,
+                    parts,
+                })
+            })
+            .collect()
+    }
+
+    pub fn sum_gear_ratios(&self, rules: &Rules) -> Result<u64, NumberError> {
+        self.gears(rules).into_iter().try_fold(0u64, |sum, gear| {
+            sum.checked_add(gear.ratio?).ok_or(NumberError::Sum)
+        })
+    }
+}
+
+/// A gear symbol together with the parts that make it a gear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gear {
+    pub row: usize,
+    pub column: usize,
+    pub symbol: char,
+    /// Ids of the adjacent parts, in reading order.
+    pub parts: Vec<usize>,
+    /// An error if a part number or the ratio itself doesn't fit in a `u64`.
+    pub ratio: Result<u64, NumberError>,
+}
+
+/// Which characters count as symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolSet {
+    /// ASCII punctuation other than `.`, as in the puzzle.
+    Punctuation,
+    /// Anything that isn't a digit, `.` or whitespace, including non-ASCII.
+    Any,
+    /// Exactly these characters.
+    Chars(Vec<char>),
+}
+
+impl SymbolSet {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            SymbolSet::Punctuation => c.is_ascii_punctuation() && c != '.',
+            SymbolSet::Any => !c.is_ascii_digit() && c != '.' && !c.is_whitespace(),
+            SymbolSet::Chars(chars) => chars.contains(&c),
+        }
+    }
+}
+
+impl FromStr for SymbolSet {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<SymbolSet, String> {
+        match input {
+            "punctuation" => Ok(SymbolSet::Punctuation),
+            "any" => Ok(SymbolSet::Any),
+            "" => Err("symbol set can't be empty".to_string()),
+            chars => Ok(SymbolSet::Chars(chars.chars().collect())),
+        }
+    }
+}
+
+/// How many adjacent parts a gear needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, n: usize) -> bool {
+        match *self {
+            Arity::Exactly(arity) => n == arity,
+            Arity::AtLeast(arity) => n >= arity,
+        }
+    }
+}
+
+impl FromStr for Arity {
+    type Err = String;
+
+    /// `2` means exactly two parts, `2+` means two or more.
+    fn from_str(input: &str) -> Result<Arity, String> {
+        let invalid = |_| format!("invalid arity {:?}", input);
+        match input.strip_suffix('+') {
+            Some(n) => n.parse().map(Arity::AtLeast).map_err(invalid),
+            None => input.parse().map(Arity::Exactly).map_err(invalid),
+        }
+    }
+}
+
+/// How a gear's part numbers combine into its ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Product,
+    Sum,
+}
+
+impl Combine {
+    /// `None` if the result doesn't fit in a `u64`.
+    pub fn apply<I: Iterator<Item = u64>>(&self, mut values: I) -> Option<u64> {
+        match self {
+            Combine::Product => values.try_fold(1, u64::checked_mul),
+            Combine::Sum => values.try_fold(0, u64::checked_add),
+        }
+    }
+}
+
+impl FromStr for Combine {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Combine, String> {
+        match input {
+            "product" => Ok(Combine::Product),
+            "sum" => Ok(Combine::Sum),
+            _ => Err(format!("unknown ratio {:?}", input)),
+        }
+    }
+}
+
+/// Everything that decides what counts as a part number and as a gear.
+/// The default matches the puzzle: ASCII punctuation symbols, and `*` gears
+/// with exactly two parts whose ratio is their product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub symbols: SymbolSet,
+    pub gears: Vec<char>,
+    pub arity: Arity,
+    pub ratio: Combine,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            symbols: SymbolSet::Punctuation,
+            gears: vec!['*'],
+            arity: Arity::Exactly(2),
+            ratio: Combine::Product,
+        }
+    }
+}
+
+impl Rules {
+    /// Set one rule by name, as used by the `--symbols`, `--gears`, `--arity`
+    /// and `--ratio` command line flags.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "symbols" => self.symbols = value.parse()?,
+            "gears" if value.is_empty() => return Err("gears can't be empty".to_string()),
+            "gears" => self.gears = value.chars().collect(),
+            "arity" => self.arity = value.parse()?,
+            "ratio" => self.ratio = value.parse()?,
+            _ => return Err(format!("unknown rule {:?}", name)),
+        }
+        Ok(())
+    }
+}
+
+/// Find every run of digits in the schematic.
+pub fn read_data(schematic: &str) -> Vec<PartInfo> {
+    let mut parts: Vec<PartInfo> = Vec::new();
+
+    for (row_index, line) in schematic.lines().enumerate() {
+        let mut start_index: Option<usize> = None;
+        let mut value = String::new();
+
+        for (x, char) in line.chars().enumerate() {
+            if char.is_ascii_digit() {
+                start_index.get_or_insert(x);
+                value.push(char);
+            } else if let Some(column_index) = start_index.take() {
+                parts.push(PartInfo {
+                    column_index,
+                    row_index,
+                    length: value.len(),
+                    value: value.clone(),
+                });
+                value.clear();
+            }
+        }
+
+        if let Some(column_index) = start_index {
+            parts.push(PartInfo {
+                column_index,
+                row_index,
+                length: value.len(),
+                value,
+            });
+        }
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_read_data() {
+        let parts = read_data("467..114\n..35...1");
+        assert_eq!(parts.len(), 4);
+        assert_eq!(
+            parts[1],
+            PartInfo {
+                column_index: 5,
+                row_index: 0,
+                length: 3,
+                value: "114".to_string()
+            }
+        );
+        assert_eq!(parts[3].column_index, 7);
+        assert_eq!(parts[3].number(), Ok(1));
+    }
+
+    #[test]
+    fn test_part_index() {
+        let schematic = Schematic::new(indoc! {"
+            467..114
+            ...*....
+            ..35....
+        "});
+
+        assert_eq!(schematic.part_at(0, 0), Some(0));
+        assert_eq!(schematic.part_at(0, 2), Some(0));
+        assert_eq!(schematic.part_at(0, 3), None);
+        assert_eq!(schematic.part_at(2, 3), Some(2));
+        assert_eq!(schematic.part_at(9, 9), None);
+        assert_eq!(schematic.adjacent_parts(1, 3), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_neighbours_at_borders() {
+        let schematic = Schematic::new("abc\nde\nfgh");
+
+        let corner: Vec<(usize, usize)> = schematic.neighbours(0, 0).collect();
+        assert_eq!(corner, vec![(0, 1), (1, 0), (1, 1)]);
+
+        // (1, 2) is past the end of the short middle row
+        let edge: Vec<(usize, usize)> = schematic.neighbours(0, 2).collect();
+        assert_eq!(edge, vec![(0, 1), (1, 1)]);
+
+        let corner: Vec<(usize, usize)> = schematic.neighbours(2, 2).collect();
+        assert_eq!(corner, vec![(1, 1), (2, 1)]);
+
+        assert_eq!(schematic.neighbours(1, 1).count(), 7);
+    }
+
+    #[test]
+    fn test_part_neighbours() {
+        let schematic = Schematic::new("#12\n...");
+        let mut cells = schematic.part_neighbours(0);
+        cells.sort();
+        assert_eq!(cells, vec![(0, 0), (1, 0), (1, 1), (1, 2)]);
+        assert!(schematic.is_part_number(0, &Rules::default()));
+    }
+
+    #[test]
+    fn test_symbol_sets() {
+        let schematic = Schematic::new("1§..2#..3*");
+        let mut rules = Rules::default();
+        assert_eq!(schematic.sum_part_numbers(&rules), Ok(5));
+
+        rules.set("symbols", "any").unwrap();
+        assert_eq!(schematic.sum_part_numbers(&rules), Ok(6));
+
+        rules.set("symbols", "§*").unwrap();
+        assert_eq!(schematic.sum_part_numbers(&rules), Ok(4));
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        let schematic = Schematic::new(indoc! {"
+            2.3...4.
+            .*....#.
+            5.....6.
+        "});
+        let mut rules = Rules::default();
+        assert_eq!(schematic.gears(&rules), vec![]);
+
+        rules.set("arity", "3+").unwrap();
+        let gears = schematic.gears(&rules);
+        assert_eq!(gears.len(), 1);
+        assert_eq!(
+            (gears[0].row, gears[0].column, gears[0].symbol),
+            (1, 1, '*')
+        );
+        assert_eq!(gears[0].parts, vec![0, 1, 3]);
+        assert_eq!(gears[0].ratio, Ok(30));
+
+        rules.set("gears", "*#").unwrap();
+        rules.set("arity", "2").unwrap();
+        rules.set("ratio", "sum").unwrap();
+        assert_eq!(schematic.sum_gear_ratios(&rules), Ok(10));
+
+        rules.set("arity", "2+").unwrap();
+        assert_eq!(schematic.sum_gear_ratios(&rules), Ok(20));
+    }
+
+    #[test]
+    fn test_numbers_past_u64() {
+        let rules = Rules::default();
+        let long = Schematic::new("99999999999999999999*1");
+        let error = NumberError::PartNumber { row: 0, column: 0 };
+        assert_eq!(long.parts()[0].number(), Err(error.clone()));
+        assert_eq!(long.sum_part_numbers(&rules), Err(error.clone()));
+        assert_eq!(long.sum_gear_ratios(&rules), Err(error));
+
+        // 2^32 * 2^32
+        let product = Schematic::new("4294967296*4294967296");
+        assert_eq!(product.sum_part_numbers(&rules), Ok(1 << 33));
+        assert_eq!(
+            product.sum_gear_ratios(&rules),
+            Err(NumberError::GearRatio { row: 0, column: 10 })
+        );
+
+        let sum = Schematic::new("18446744073709551615*1");
+        assert_eq!(sum.sum_part_numbers(&rules), Err(NumberError::Sum));
+        assert_eq!(sum.sum_gear_ratios(&rules), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_rules_from_str() {
+        assert_eq!("3".parse(), Ok(Arity::Exactly(3)));
+        assert_eq!("1+".parse(), Ok(Arity::AtLeast(1)));
+        assert!("two".parse::<Arity>().is_err());
+        assert_eq!("sum".parse(), Ok(Combine::Sum));
+        assert_eq!(
+            Combine::Product.apply([u64::MAX, 1].into_iter()),
+            Some(u64::MAX)
+        );
+        assert_eq!(Combine::Product.apply([1 << 32, 1 << 32].into_iter()), None);
+        assert_eq!(Combine::Sum.apply([u64::MAX, 1].into_iter()), None);
+        assert!("mean".parse::<Combine>().is_err());
+        assert_eq!("#§".parse(), Ok(SymbolSet::Chars(vec!['#', '§'])));
+
+        let mut rules = Rules::default();
+        assert!(rules.set("gears", "").is_err());
+        assert!(rules.set("colour", "red").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! The schematic as a bipartite graph: part numbers on one side, symbols on
+//! the other, with an edge wherever a symbol touches a part.
+
+use super::{Rules, Schematic};
+use crate::export::json_string;
+use std::io::{self, Write};
+
+/// A symbol in the schematic and the parts touching it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolNode {
+    pub row: usize,
+    pub column: usize,
+    pub symbol: char,
+    /// Ids of the adjacent parts, in reading order.
+    pub parts: Vec<usize>,
+}
+
+pub struct PartGraph<'a> {
+    schematic: &'a Schematic,
+    symbols: Vec<SymbolNode>,
+}
+
+impl<'a> PartGraph<'a> {
+    pub fn new(schematic: &'a Schematic, rules: &Rules) -> PartGraph<'a> {
+        let symbols = schematic
+            .cells()
+            .filter(|&(_, _, c)| rules.symbols.contains(c))
+            .map(|(row, column, symbol)| SymbolNode {
+                row,
+                column,
+                symbol,
+                parts: schematic.adjacent_parts(row, column),
+            })
+            .collect();
+
+        PartGraph { schematic, symbols }
+    }
+
+    pub fn symbols(&self) -> &[SymbolNode] {
+        &self.symbols
+    }
+
+    /// `(symbol index, part id)` for every symbol/part adjacency.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.symbols
+            .iter()
+            .enumerate()
+            .flat_map(|(i, symbol)| symbol.parts.iter().map(move |&part| (i, part)))
+    }
+
+    /// Groups of part ids connected through shared symbols. Parts that touch no
+    /// symbol are left out. Components are ordered by their lowest part id.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let n_parts = self.schematic.parts().len();
+        let mut parent: Vec<usize> = (0..n_parts).collect();
+
+        for symbol in &self.symbols {
+            for pair in symbol.parts.windows(2) {
+                let a = find(&mut parent, pair[0]);
+                let b = find(&mut parent, pair[1]);
+                if a != b {
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut attached = vec![false; n_parts];
+        for (_, part) in self.edges() {
+            attached[part] = true;
+        }
+
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        let mut component_of: Vec<Option<usize>> = vec![None; n_parts];
+        for part in (0..n_parts).filter(|&part| attached[part]) {
+            let root = find(&mut parent, part);
+            match component_of[root] {
+                Some(i) => components[i].push(part),
+                None => {
+                    component_of[root] = Some(components.len());
+                    components.push(vec![part]);
+                }
+            }
+        }
+
+        components
+    }
+
+    pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let parts = self.schematic.parts();
+
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"parts\": [")?;
+        for (id, part) in parts.iter().enumerate() {
+            write!(
+                out,
+                "    {{\"id\": {}, \"row\": {}, \"column\": {}, \"length\": {}, \"value\": {}}}",
+                id,
+                part.row_index,
+                part.column_index,
+                part.length,
+                json_number(&part.value)
+            )?;
+            writeln!(out, "{}", comma(id, parts.len()))?;
+        }
+        writeln!(out, "  ],")?;
+
+        writeln!(out, "  \"symbols\": [")?;
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            let values: Vec<String> = symbol
+                .parts
+                .iter()
+                .map(|&id| json_number(&parts[id].value).to_string())
+                .collect();
+            write!(
+                out,
+                "    {{\"id\": {}, \"row\": {}, \"column\": {}, \"symbol\": {}, \"parts\": {}, \"values\": [{}]}}",
+                i,
+                symbol.row,
+                symbol.column,
+                json_string(&symbol.symbol.to_string()),
+                json_list(&symbol.parts),
+                values.join(", ")
+            )?;
+            writeln!(out, "{}", comma(i, self.symbols.len()))?;
+        }
+        writeln!(out, "  ],")?;
+
+        let edges: Vec<String> = self
+            .edges()
+            .map(|(symbol, part)| format!("[{}, {}]", symbol, part))
+            .collect();
+        writeln!(out, "  \"edges\": [{}],", edges.join(", "))?;
+
+        let components: Vec<String> = self
+            .components()
+            .iter()
+            .map(|component| json_list(component))
+            .collect();
+        writeln!(out, "  \"components\": [{}]", components.join(", "))?;
+        writeln!(out, "}}")
+    }
+
+    /// Parts are boxes `p<id>`, symbols are circles `s<index>`.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "graph schematic {{")?;
+        for (id, part) in self.schematic.parts().iter().enumerate() {
+            writeln!(
+                out,
+                "  p{} [shape=box, label={}];",
+                id,
+                json_string(&part.value)
+            )?;
+        }
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            writeln!(
+                out,
+                "  s{} [shape=circle, label={}];",
+                i,
+                json_string(&symbol.symbol.to_string())
+            )?;
+        }
+        for (symbol, part) in self.edges() {
+            writeln!(out, "  s{} -- p{};", symbol, part)?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+fn find(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+fn json_list(values: &[usize]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// A part's digits as a JSON number: no leading zeros, and no limit on size.
+fn json_number(digits: &str) -> &str {
+    match digits.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    }
+}
+
+fn comma(i: usize, len: usize) -> &'static str {
+    if i + 1 < len {
+        ","
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        467..114..
+        ...*......
+        ..35..633.
+        ......#...
+        617*......
+        .....+.58.
+        ..592.....
+        ......755.
+        ...$.*....
+        .664.598..
+    "};
+
+    #[test]
+    fn test_symbols_and_edges() {
+        let schematic = Schematic::new(INPUT);
+        let graph = PartGraph::new(&schematic, &Rules::default());
+
+        let symbols: Vec<char> = graph.symbols().iter().map(|s| s.symbol).collect();
+        assert_eq!(symbols, vec!['*', '#', '*', '+', '$', '*']);
+        assert_eq!(graph.symbols()[0].parts, vec![0, 2]);
+        assert_eq!(graph.edges().count(), 8);
+    }
+
+    #[test]
+    fn test_components() {
+        let schematic = Schematic::new(INPUT);
+        let graph = PartGraph::new(&schematic, &Rules::default());
+
+        let values: Vec<Vec<u64>> = graph
+            .components()
+            .iter()
+            .map(|component| {
+                component
+                    .iter()
+                    .map(|&id| schematic.parts()[id].number().unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![467, 35],
+                vec![633],
+                vec![617],
+                vec![592],
+                vec![755, 598],
+                vec![664]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_components_chain_through_symbols() {
+        // 1 and 2 share `*`, 2 and 3 share `#`
+        let schematic = Schematic::new("1*2#3\n.....\n9....");
+        let graph = PartGraph::new(&schematic, &Rules::default());
+        assert_eq!(graph.components(), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let schematic = Schematic::new("12*\n..3");
+        let graph = PartGraph::new(&schematic, &Rules::default());
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot).unwrap();
+        assert_eq!(
+            String::from_utf8(dot).unwrap(),
+            indoc! {r#"
+                graph schematic {
+                  p0 [shape=box, label="12"];
+                  p1 [shape=box, label="3"];
+                  s0 [shape=circle, label="*"];
+                  s0 -- p0;
+                  s0 -- p1;
+                }
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let schematic = Schematic::new("12*\n..3");
+        let graph = PartGraph::new(&schematic, &Rules::default());
+
+        let mut json = Vec::new();
+        graph.write_json(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            indoc! {r#"
+                {
+                  "parts": [
+                    {"id": 0, "row": 0, "column": 0, "length": 2, "value": 12},
+                    {"id": 1, "row": 1, "column": 2, "length": 1, "value": 3}
+                  ],
+                  "symbols": [
+                    {"id": 0, "row": 0, "column": 2, "symbol": "*", "parts": [0, 1], "values": [12, 3]}
+                  ],
+                  "edges": [[0, 0], [0, 1]],
+                  "components": [[0, 1]]
+                }
+            "#}
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Draw the schematic with part numbers and gears highlighted, to check the
+//! adjacency rules by eye.
+
+use super::{Rules, Schematic};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Plain,
+    /// A digit of a number that touches a symbol.
+    Accepted,
+    /// A digit of a number that touches no symbol.
+    Rejected,
+    Symbol,
+    Gear,
+}
+
+impl Highlight {
+    fn ansi(&self) -> Option<&'static str> {
+        match self {
+            Highlight::Plain => None,
+            Highlight::Accepted => Some("\x1b[32m"),
+            Highlight::Rejected => Some("\x1b[31m"),
+            Highlight::Symbol => Some("\x1b[36m"),
+            Highlight::Gear => Some("\x1b[1;33m"),
+        }
+    }
+
+    fn class(&self) -> Option<&'static str> {
+        match self {
+            Highlight::Plain => None,
+            Highlight::Accepted => Some("accepted"),
+            Highlight::Rejected => Some("rejected"),
+            Highlight::Symbol => Some("symbol"),
+            Highlight::Gear => Some("gear"),
+        }
+    }
+}
+
+/// How every cell of the schematic should be drawn, row by row.
+pub fn classify(schematic: &Schematic, rules: &Rules) -> Vec<Vec<Highlight>> {
+    let mut cells: Vec<Vec<Highlight>> = schematic
+        .rows()
+        .iter()
+        .map(|row| vec![Highlight::Plain; row.len()])
+        .collect();
+
+    for (row, column, c) in schematic.cells() {
+        if rules.symbols.contains(c) {
+            cells[row][column] = Highlight::Symbol;
+        }
+    }
+    for (id, part) in schematic.parts().iter().enumerate() {
+        let highlight = if schematic.is_part_number(id, rules) {
+            Highlight::Accepted
+        } else {
+            Highlight::Rejected
+        };
+        let columns = part.column_index..part.column_index + part.length;
+        for cell in &mut cells[part.row_index][columns] {
+            *cell = highlight;
+        }
+    }
+    for gear in schematic.gears(rules) {
+        cells[gear.row][gear.column] = Highlight::Gear;
+    }
+
+    cells
+}
+
+/// Write the schematic with ANSI colours, for a terminal.
+pub fn write_ansi<W: Write>(out: &mut W, schematic: &Schematic, rules: &Rules) -> io::Result<()> {
+    let highlights = classify(schematic, rules);
+    for (row, line) in schematic.rows().iter().enumerate() {
+        for (column, c) in line.iter().enumerate() {
+            match highlights[row][column].ansi() {
+                Some(colour) => write!(out, "{}{}\x1b[0m", colour, c)?,
+                None => write!(out, "{}", c)?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write the schematic as a standalone HTML page.
+pub fn write_html<W: Write>(out: &mut W, schematic: &Schematic, rules: &Rules) -> io::Result<()> {
+    let highlights = classify(schematic, rules);
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Engine schematic</title>")?;
+    writeln!(out, "<style>")?;
+    writeln!(out, "body {{ background: #10101a; color: #666; }}")?;
+    writeln!(out, ".accepted {{ color: #3c3; }}")?;
+    writeln!(out, ".rejected {{ color: #e33; }}")?;
+    writeln!(out, ".symbol {{ color: #3cc; }}")?;
+    writeln!(out, ".gear {{ color: #fd3; font-weight: bold; }}")?;
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    write!(out, "<pre>")?;
+    for (row, line) in schematic.rows().iter().enumerate() {
+        for (column, c) in line.iter().enumerate() {
+            let c = escape_html(*c);
+            match highlights[row][column].class() {
+                Some(class) => write!(out, "<span class=\"{}\">{}</span>", class, c)?,
+                None => write!(out, "{}", c)?,
+            }
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "</pre>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+fn escape_html(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let schematic = Schematic::new("1*2.3\n..#..");
+        let highlights = classify(&schematic, &Rules::default());
+
+        use Highlight::*;
+        assert_eq!(
+            highlights[0],
+            vec![Accepted, Gear, Accepted, Plain, Rejected]
+        );
+        assert_eq!(highlights[1], vec![Plain, Plain, Symbol, Plain, Plain]);
+
+        let schematic = Schematic::new("12..7\n*...\n3");
+        let highlights = classify(&schematic, &Rules::default());
+        assert_eq!(
+            highlights[0],
+            vec![Accepted, Accepted, Plain, Plain, Rejected]
+        );
+        assert_eq!(highlights[1], vec![Gear, Plain, Plain, Plain]);
+        assert_eq!(highlights[2], vec![Accepted]);
+    }
+
+    #[test]
+    fn test_write_ansi() {
+        let schematic = Schematic::new("4#.5");
+        let mut out = Vec::new();
+        write_ansi(&mut out, &schematic, &Rules::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[32m4\x1b[0m\x1b[36m#\x1b[0m.\x1b[31m5\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_write_html() {
+        let schematic = Schematic::new("4&.5");
+        let mut out = Vec::new();
+        write_html(&mut out, &schematic, &Rules::default()).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains(
+            "<pre><span class=\"accepted\">4</span><span class=\"symbol\">&amp;</span>.<span class=\"rejected\">5</span>\n</pre>"
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+mod numbers;
+pub mod provenance;
+mod rules;
+
+pub use numbers::NumberSet;
+pub use rules::{CopyRule, Scoring};
+
+use num::{BigUint, One};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+pub struct Card {
+    pub card_id: u32,
+    pub winning_numbers: NumberSet,
+    pub game_numbers: NumberSet,
+}
+
+impl Card {
+    pub fn new(card_id: u32, winning_numbers: NumberSet, game_numbers: NumberSet) -> Card {
+        Card {
+            card_id,
+            winning_numbers,
+            game_numbers,
+        }
+    }
+
+    /// How many of our numbers are winning numbers.
+    pub fn matches(&self) -> u32 {
+        self.winning_numbers.intersection_len(&self.game_numbers) as u32
+    }
+
+    pub fn score(&self, scoring: &Scoring) -> u64 {
+        scoring.score(self.matches())
+    }
+
+    /// Ids of the cards this card wins a copy of. Ids that would overflow a
+    /// `u32` are dropped.
+    pub fn won_cards(&self, rule: &CopyRule) -> Vec<u32> {
+        rule.offsets(self.matches())
+            .into_iter()
+            .filter_map(|offset| self.card_id.checked_add(offset))
+            .collect()
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ID: {}\nWinning Numbers: {:?}\nGame Numbers: {:?}",
+            self.card_id, self.winning_numbers, self.game_numbers
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    MissingHeader(String),
+    InvalidId(String),
+    MissingSeparator(String),
+    InvalidNumber(String),
+    /// The same number appears twice on one side of a card.
+    DuplicateNumber {
+        card_id: u32,
+        number: u32,
+    },
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCardError::MissingHeader(line) => {
+                write!(f, "missing `Card <id>:` in {:?}", line)
+            }
+            ParseCardError::InvalidId(header) => write!(f, "invalid card id in {:?}", header),
+            ParseCardError::MissingSeparator(line) => write!(f, "missing `|` in {:?}", line),
+            ParseCardError::InvalidNumber(number) => write!(f, "invalid number {:?}", number),
+            ParseCardError::DuplicateNumber { card_id, number } => {
+                write!(f, "card {} lists {} more than once", card_id, number)
+            }
+        }
+    }
+}
+
+impl Error for ParseCardError {}
+
+pub fn scan_cards(input: &str) -> Result<Vec<Card>, ParseCardError> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_line)
+        .collect()
+}
+
+pub fn parse_line(line: &str) -> Result<Card, ParseCardError> {
+    // Get the card number
+    let (header, card_values) = line
+        .split_once(':')
+        .ok_or_else(|| ParseCardError::MissingHeader(line.to_string()))?;
+    let card_id = parse_card_id(header)
+        .ok_or_else(|| ParseCardError::InvalidId(header.trim().to_string()))?;
+
+    // Split the line by "|"
+    let (winning, game) = card_values
+        .split_once('|')
+        .ok_or_else(|| ParseCardError::MissingSeparator(line.to_string()))?;
+
+    let winning_numbers = parse_numbers(card_id, winning)?;
+    let game_numbers = parse_numbers(card_id, game)?;
+
+    Ok(Card::new(card_id, winning_numbers, game_numbers))
+}
+
+fn parse_card_id(header: &str) -> Option<u32> {
+    let mut iter = header.split_whitespace();
+
+    if iter.next()? != "Card" {
+        return None;
+    }
+    let id = iter.next()?.parse::<u32>().ok()?;
+    if iter.next().is_some() {
+        return None;
+    }
+
+    Some(id)
+}
+
+fn parse_numbers(card_id: u32, input: &str) -> Result<NumberSet, ParseCardError> {
+    let mut numbers = NumberSet::new();
+    for num_str in input.split_whitespace() {
+        let number = num_str
+            .parse::<u32>()
+            .map_err(|_| ParseCardError::InvalidNumber(num_str.to_string()))?;
+        if !numbers.insert(number) {
+            return Err(ParseCardError::DuplicateNumber { card_id, number });
+        }
+    }
+    Ok(numbers)
+}
+
+/// Something wrong with the table of cards as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// Card `card_id` shows up after the higher-numbered `previous`.
+    OutOfOrder {
+        previous: u32,
+        card_id: u32,
+    },
+    /// Card ids jump from `previous` straight to `card_id`.
+    Gap {
+        previous: u32,
+        card_id: u32,
+    },
+    DuplicateId(u32),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::OutOfOrder { previous, card_id } => {
+                write!(f, "card {} comes after card {}", card_id, previous)
+            }
+            TableError::Gap { previous, card_id } => {
+                write!(f, "cards {} to {} are missing", previous + 1, card_id - 1)
+            }
+            TableError::DuplicateId(card_id) => write!(f, "card {} appears twice", card_id),
+        }
+    }
+}
+
+impl Error for TableError {}
+
+/// Check that the card ids run upwards one at a time with nothing missing or repeated.
+pub fn validate_table(cards: &[Card]) -> Result<(), TableError> {
+    for pair in cards.windows(2) {
+        let (previous, card_id) = (pair[0].card_id, pair[1].card_id);
+        if card_id == previous {
+            return Err(TableError::DuplicateId(card_id));
+        }
+        if card_id < previous {
+            if cards.iter().filter(|card| card.card_id == card_id).count() > 1 {
+                return Err(TableError::DuplicateId(card_id));
+            }
+            return Err(TableError::OutOfOrder { previous, card_id });
+        }
+        if card_id > previous + 1 {
+            return Err(TableError::Gap { previous, card_id });
+        }
+    }
+    Ok(())
+}
+
+/// What to do when a card near the end of the table wins copies of cards
+/// that don't exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndPolicy {
+    /// Only award copies of the cards that exist.
+    #[default]
+    Clamp,
+    /// Refuse to count the table.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountError {
+    /// Card `card_id` won a copy of `won_id`, but the table stops at `last_id`.
+    PastEnd {
+        card_id: u32,
+        won_id: u32,
+        last_id: u32,
+    },
+    /// A card won a copy of a card which isn't in the table even though
+    /// higher-numbered cards are.
+    MissingCard(u32),
+    DuplicateId(u32),
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountError::PastEnd {
+                card_id,
+                won_id,
+                last_id,
+            } => write!(
+                f,
+                "card {} wins card {} but the table ends at card {}",
+                card_id, won_id, last_id
+            ),
+            CountError::MissingCard(card_id) => write!(f, "card {} is missing", card_id),
+            CountError::DuplicateId(card_id) => write!(f, "card {} appears twice", card_id),
+        }
+    }
+}
+
+impl Error for CountError {}
+
+/// Total number of cards held once every copy has been scratched.
+///
+/// `rule` says which cards each card wins copies of, looked up by id, so the
+/// table doesn't need to be in order. Cards only ever win later cards, and
+/// every copy of a card wins the same ones, so all of its copies are passed on
+/// in one go instead of one at a time: the work is proportional to the number
+/// of cards times their matches, no matter how many copies pile up.
+pub fn count_cards(
+    cards: &[Card],
+    rule: &CopyRule,
+    policy: EndPolicy,
+) -> Result<BigUint, CountError> {
+    let table = copy_table(cards, rule, policy)?;
+    let mut copies: BTreeMap<u32, BigUint> = table
+        .keys()
+        .map(|&card_id| (card_id, BigUint::one()))
+        .collect();
+
+    for (card_id, (_, won)) in &table {
+        let count = copies[card_id].clone();
+        for won_id in won {
+            *copies.get_mut(won_id).unwrap() += &count;
+        }
+    }
+
+    Ok(copies.values().sum())
+}
+
+/// Every card by id, with the ids of the cards it wins a copy of. Wins past
+/// the last card are dropped or refused according to `policy`.
+fn copy_table<'a>(
+    cards: &'a [Card],
+    rule: &CopyRule,
+    policy: EndPolicy,
+) -> Result<BTreeMap<u32, (&'a Card, Vec<u32>)>, CountError> {
+    let mut table: BTreeMap<u32, (&Card, Vec<u32>)> = BTreeMap::new();
+    for card in cards {
+        if table.insert(card.card_id, (card, Vec::new())).is_some() {
+            return Err(CountError::DuplicateId(card.card_id));
+        }
+    }
+    let Some(&last_id) = table.keys().next_back() else {
+        return Ok(table);
+    };
+
+    let ids: Vec<u32> = table.keys().copied().collect();
+    for card_id in ids {
+        let mut won = table[&card_id].0.won_cards(rule);
+        if let Some(&won_id) = won.iter().find(|&&won_id| won_id > last_id) {
+            if policy == EndPolicy::Error {
+                return Err(CountError::PastEnd {
+                    card_id,
+                    won_id,
+                    last_id,
+                });
+            }
+        }
+        won.retain(|&won_id| won_id <= last_id);
+        if let Some(&won_id) = won.iter().find(|won_id| !table.contains_key(won_id)) {
+            return Err(CountError::MissingCard(won_id));
+        }
+        table.get_mut(&card_id).unwrap().1 = won;
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+        Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+        Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+        Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+    "};
+
+    #[test]
+    fn test_count_cards() {
+        let cards = scan_cards(INPUT).unwrap();
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp),
+            Ok(BigUint::from(30u32))
+        );
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Error),
+            Ok(BigUint::from(30u32))
+        );
+    }
+
+    #[test]
+    fn test_count_cards_past_end() {
+        let cards = scan_cards(indoc! {"
+            Card 1: 1 2 | 1 3
+            Card 2: 1 2 3 | 1 2 3
+        "})
+        .unwrap();
+
+        // Card 1 wins one copy of card 2, card 2's wins fall off the end
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp),
+            Ok(BigUint::from(3u32))
+        );
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Error),
+            Err(CountError::PastEnd {
+                card_id: 2,
+                won_id: 3,
+                last_id: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_cards_large() {
+        // Every card wins a copy of each of the next two cards, so the copies
+        // grow like the Fibonacci sequence and overflow a u64 long before the end
+        let cards: Vec<Card> = (1..=200)
+            .map(|id| parse_line(&format!("Card {}: 1 2 | 1 2", id)).unwrap())
+            .collect();
+
+        let total = count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp).unwrap();
+        assert!(total > BigUint::from(u64::MAX));
+        assert_eq!(
+            total,
+            "1923063428480944139667114773918309212080325"
+                .parse::<BigUint>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let card = parse_line("Card  12: 41 48 | 83 48").unwrap();
+        assert_eq!(card.card_id, 12);
+        assert_eq!(card.matches(), 1);
+
+        let card = parse_line("Card 2: 1000 7 3 | 3 1000 5").unwrap();
+        assert_eq!(card.matches(), 2);
+        assert_eq!(
+            parse_line("Card 3: 1000 1000 | 1").err(),
+            Some(ParseCardError::DuplicateNumber {
+                card_id: 3,
+                number: 1000
+            })
+        );
+
+        assert_eq!(
+            parse_line("41 48 | 83 48").err(),
+            Some(ParseCardError::MissingHeader("41 48 | 83 48".to_string()))
+        );
+        assert_eq!(
+            parse_line("Card x: 41 48 | 83 48").err(),
+            Some(ParseCardError::InvalidId("Card x".to_string()))
+        );
+        assert_eq!(
+            parse_line("Card 1: 41 48 83 48").err(),
+            Some(ParseCardError::MissingSeparator(
+                "Card 1: 41 48 83 48".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_line("Card 1: 41 4a | 83 48").err(),
+            Some(ParseCardError::InvalidNumber("4a".to_string()))
+        );
+        assert_eq!(
+            parse_line("Card 3: 41 48 | 83 48 83").err(),
+            Some(ParseCardError::DuplicateNumber {
+                card_id: 3,
+                number: 83
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_table() {
+        let table = |ids: &[u32]| -> Vec<Card> {
+            ids.iter()
+                .map(|id| parse_line(&format!("Card {}: 1 | 2", id)).unwrap())
+                .collect()
+        };
+
+        assert_eq!(validate_table(&table(&[1, 2, 3])), Ok(()));
+        assert_eq!(validate_table(&table(&[5, 6])), Ok(()));
+        assert_eq!(
+            validate_table(&table(&[1, 3])),
+            Err(TableError::Gap {
+                previous: 1,
+                card_id: 3
+            })
+        );
+        assert_eq!(
+            validate_table(&table(&[1, 2, 2])),
+            Err(TableError::DuplicateId(2))
+        );
+        assert_eq!(
+            validate_table(&table(&[1, 3, 2])),
+            Err(TableError::Gap {
+                previous: 1,
+                card_id: 3
+            })
+        );
+        assert_eq!(
+            validate_table(&table(&[2, 1])),
+            Err(TableError::OutOfOrder {
+                previous: 2,
+                card_id: 1
+            })
+        );
+        assert_eq!(
+            validate_table(&table(&[1, 2, 1])),
+            Err(TableError::DuplicateId(1))
+        );
+    }
+
+    #[test]
+    fn test_count_cards_by_id() {
+        let mut cards = scan_cards(INPUT).unwrap();
+        cards.reverse();
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp),
+            Ok(BigUint::from(30u32))
+        );
+
+        // Card 2 wins card 3, which isn't there
+        let cards = scan_cards("Card 1: 1 | 2\nCard 2: 1 | 1\nCard 4: 1 | 2").unwrap();
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp),
+            Err(CountError::MissingCard(3))
+        );
+
+        let cards = scan_cards("Card 1: 1 | 1\nCard 1: 1 | 2").unwrap();
+        assert_eq!(
+            count_cards(&cards, &CopyRule::Next, EndPolicy::Clamp),
+            Err(CountError::DuplicateId(1))
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let cards = scan_cards(INPUT).unwrap();
+
+        let points: Vec<u64> = cards
+            .iter()
+            .map(|card| card.score(&Scoring::Doubling))
+            .collect();
+        assert_eq!(points, vec![8, 2, 2, 1, 0, 0]);
+        let points: Vec<u64> = cards
+            .iter()
+            .map(|card| card.score(&Scoring::Linear))
+            .collect();
+        assert_eq!(points, vec![4, 2, 2, 1, 0, 0]);
+
+        assert_eq!(cards[0].won_cards(&CopyRule::Next), vec![2, 3, 4, 5]);
+        let every_other = CopyRule::Offsets(vec![2, 4, 6]);
+        assert_eq!(cards[0].won_cards(&every_other), vec![3, 5, 7]);
+        assert_eq!(cards[1].won_cards(&every_other), vec![4, 6]);
+
+        // 1 wins 3 and 5, 2 wins 4 and 6, both copies of 3 win 5 and both
+        // copies of 4 win 6; card 7 doesn't exist
+        assert_eq!(
+            count_cards(&cards, &every_other, EndPolicy::Clamp),
+            Ok(BigUint::from(14u32))
+        );
+        assert_eq!(
+            count_cards(&cards, &every_other, EndPolicy::Error),
+            Err(CountError::PastEnd {
+                card_id: 1,
+                won_id: 7,
+                last_id: 6
+            })
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! The numbers on one side of a card. Puzzle numbers are all below 100, so
+//! they fit in a fixed 128-bit bitset and counting matches is a popcount.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Numbers from `0` up to this bound fit in the bitset.
+pub const SMALL_LIMIT: u32 = 128;
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum NumberSet {
+    /// Bit `n % 64` of word `n / 64` is set when `n` is in the set.
+    Small([u64; 2]),
+    /// Sorted numbers, used once any number reaches `SMALL_LIMIT`.
+    Large(Vec<u32>),
+}
+
+impl NumberSet {
+    pub fn new() -> NumberSet {
+        NumberSet::Small([0; 2])
+    }
+
+    /// Add `number`, returning whether it wasn't there already.
+    pub fn insert(&mut self, number: u32) -> bool {
+        if number >= SMALL_LIMIT {
+            if let NumberSet::Small(_) = self {
+                *self = NumberSet::Large(self.iter().collect());
+            }
+        }
+
+        match self {
+            NumberSet::Small(words) => {
+                let (word, bit) = ((number / 64) as usize, number % 64);
+                let fresh = words[word] & (1 << bit) == 0;
+                words[word] |= 1 << bit;
+                fresh
+            }
+            NumberSet::Large(numbers) => match numbers.binary_search(&number) {
+                Ok(_) => false,
+                Err(i) => {
+                    numbers.insert(i, number);
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn contains(&self, number: u32) -> bool {
+        match self {
+            NumberSet::Small(words) => {
+                number < SMALL_LIMIT && words[(number / 64) as usize] & (1 << (number % 64)) != 0
+            }
+            NumberSet::Large(numbers) => numbers.binary_search(&number).is_ok(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            NumberSet::Small(words) => words.iter().map(|w| w.count_ones() as usize).sum(),
+            NumberSet::Large(numbers) => numbers.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many numbers are in both sets.
+    pub fn intersection_len(&self, other: &NumberSet) -> usize {
+        match (self, other) {
+            (NumberSet::Small(a), NumberSet::Small(b)) => {
+                ((a[0] & b[0]).count_ones() + (a[1] & b[1]).count_ones()) as usize
+            }
+            (NumberSet::Large(a), NumberSet::Large(b)) => merge_count(a, b),
+            (NumberSet::Small(_), NumberSet::Large(large)) => {
+                large.iter().filter(|&&n| self.contains(n)).count()
+            }
+            (NumberSet::Large(large), NumberSet::Small(_)) => {
+                large.iter().filter(|&&n| other.contains(n)).count()
+            }
+        }
+    }
+
+    /// The numbers in ascending order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            NumberSet::Small(words) => Box::new(
+                (0..SMALL_LIMIT).filter(move |&n| words[(n / 64) as usize] & (1 << (n % 64)) != 0),
+            ),
+            NumberSet::Large(numbers) => Box::new(numbers.iter().copied()),
+        }
+    }
+}
+
+/// Common elements of two sorted slices.
+fn merge_count(a: &[u32], b: &[u32]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+impl Default for NumberSet {
+    fn default() -> Self {
+        NumberSet::new()
+    }
+}
+
+impl fmt::Debug for NumberSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<u32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut set = NumberSet::new();
+        for number in iter {
+            set.insert(number);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small() {
+        let mut set = NumberSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(3));
+        assert!(set.insert(99));
+        assert!(set.insert(127));
+        assert!(!set.insert(99));
+
+        assert!(matches!(set, NumberSet::Small(_)));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(127));
+        assert!(!set.contains(4));
+        assert!(!set.contains(1000));
+        assert_eq!(format!("{:?}", set), "{3, 99, 127}");
+    }
+
+    #[test]
+    fn test_large_fallback() {
+        let mut set: NumberSet = [5, 64].into_iter().collect();
+        assert!(set.insert(128));
+        assert!(!set.insert(5));
+
+        assert!(matches!(set, NumberSet::Large(_)));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![5, 64, 128]);
+    }
+
+    #[test]
+    fn test_intersection_len() {
+        let a: NumberSet = [1, 2, 63, 64, 100].into_iter().collect();
+        let b: NumberSet = [2, 64, 100, 101].into_iter().collect();
+        assert_eq!(a.intersection_len(&b), 3);
+
+        // Mixed representations agree with the bitset
+        let large: NumberSet = [2, 64, 100, 101, 5000].into_iter().collect();
+        assert_eq!(a.intersection_len(&large), 3);
+        assert_eq!(large.intersection_len(&a), 3);
+    }
+}
@@ -0,0 +1,294 @@
+//! Where the copies come from: the cards as a DAG with an edge from every card
+//! to each card it wins a copy of.
+
+use super::{copy_table, Card, CopyRule, CountError, EndPolicy};
+use crate::export::json_string;
+use num::{BigUint, One};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardNode {
+    pub card_id: u32,
+    pub matches: u32,
+    /// Ids of the cards this card wins a copy of.
+    pub wins: Vec<u32>,
+    /// How many of this card are held at the end.
+    pub copies: BigUint,
+    /// How many cards the original of this card accounts for: itself, the
+    /// copies it wins, the copies those win, and so on.
+    pub contribution: BigUint,
+    /// Length of the longest chain of wins starting here, in edges.
+    pub depth: usize,
+}
+
+#[derive(Default)]
+pub struct Provenance {
+    /// In ascending order of id.
+    nodes: Vec<CardNode>,
+}
+
+impl Provenance {
+    pub fn new(
+        cards: &[Card],
+        rule: &CopyRule,
+        policy: EndPolicy,
+    ) -> Result<Provenance, CountError> {
+        let table = copy_table(cards, rule, policy)?;
+        let mut nodes: Vec<CardNode> = table
+            .iter()
+            .map(|(&card_id, (card, wins))| CardNode {
+                card_id,
+                matches: card.matches(),
+                wins: wins.clone(),
+                copies: BigUint::one(),
+                contribution: BigUint::one(),
+                depth: 0,
+            })
+            .collect();
+        let index: BTreeMap<u32, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.card_id, i))
+            .collect();
+
+        // Wins only point at later cards, so a forward pass settles the copies
+        // and a backward pass the contributions and depths
+        for i in 0..nodes.len() {
+            let copies = nodes[i].copies.clone();
+            for won_id in nodes[i].wins.clone() {
+                nodes[index[&won_id]].copies += &copies;
+            }
+        }
+        for i in (0..nodes.len()).rev() {
+            let wins: Vec<usize> = nodes[i].wins.iter().map(|id| index[id]).collect();
+            for &j in &wins {
+                let contribution = nodes[j].contribution.clone();
+                nodes[i].contribution += contribution;
+            }
+            nodes[i].depth = wins.iter().map(|&j| nodes[j].depth + 1).max().unwrap_or(0);
+        }
+
+        Ok(Provenance { nodes })
+    }
+
+    pub fn nodes(&self) -> &[CardNode] {
+        &self.nodes
+    }
+
+    pub fn total(&self) -> BigUint {
+        self.nodes.iter().map(|node| &node.copies).sum()
+    }
+
+    /// Card ids along the longest cascade of wins. Ties go to the lowest ids.
+    pub fn longest_chain(&self) -> Vec<u32> {
+        let Some(start) = self.nodes.iter().max_by(|a, b| {
+            a.depth
+                .cmp(&b.depth)
+                .then_with(|| b.card_id.cmp(&a.card_id))
+        }) else {
+            return Vec::new();
+        };
+
+        let mut chain = vec![start.card_id];
+        let mut node = start;
+        while node.depth > 0 {
+            node = node
+                .wins
+                .iter()
+                .map(|id| self.node(*id))
+                .find(|next| next.depth + 1 == node.depth)
+                .unwrap();
+            chain.push(node.card_id);
+        }
+        chain
+    }
+
+    /// Number of cards with each match count.
+    pub fn histogram(&self) -> BTreeMap<u32, usize> {
+        let mut histogram = BTreeMap::new();
+        for node in &self.nodes {
+            *histogram.entry(node.matches).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    fn node(&self, card_id: u32) -> &CardNode {
+        let i = self
+            .nodes
+            .binary_search_by_key(&card_id, |node| node.card_id)
+            .unwrap();
+        &self.nodes[i]
+    }
+
+    pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(
+            out,
+            "  \"total\": {},",
+            json_string(&self.total().to_string())
+        )?;
+        writeln!(out, "  \"cards\": [")?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let wins: Vec<String> = node.wins.iter().map(|id| id.to_string()).collect();
+            // Copies can outgrow what a JSON reader will hold in a number
+            write!(
+                out,
+                "    {{\"id\": {}, \"matches\": {}, \"wins\": [{}], \"copies\": {}, \"contribution\": {}, \"depth\": {}}}",
+                node.card_id,
+                node.matches,
+                wins.join(", "),
+                json_string(&node.copies.to_string()),
+                json_string(&node.contribution.to_string()),
+                node.depth
+            )?;
+            writeln!(out, "{}", if i + 1 < self.nodes.len() { "," } else { "" })?;
+        }
+        writeln!(out, "  ],")?;
+
+        let chain: Vec<String> = self
+            .longest_chain()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        writeln!(out, "  \"longest_chain\": [{}],", chain.join(", "))?;
+
+        let histogram: Vec<String> = self
+            .histogram()
+            .iter()
+            .map(|(matches, count)| format!("{}: {}", json_string(&matches.to_string()), count))
+            .collect();
+        writeln!(out, "  \"histogram\": {{{}}}", histogram.join(", "))?;
+        writeln!(out, "}}")
+    }
+
+    /// Cards are nodes `c<id>` labelled with their final number of copies, and edges on the
+    /// longest chain are drawn bold.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let chain = self.longest_chain();
+        let on_chain = |from: u32, to: u32| chain.windows(2).any(|pair| pair == [from, to]);
+
+        writeln!(out, "digraph cards {{")?;
+        for node in &self.nodes {
+            let label = format!("Card {} x{}", node.card_id, node.copies);
+            writeln!(out, "  c{} [label={}];", node.card_id, json_string(&label))?;
+        }
+        for node in &self.nodes {
+            for &won_id in &node.wins {
+                if on_chain(node.card_id, won_id) {
+                    writeln!(out, "  c{} -> c{} [style=bold];", node.card_id, won_id)?;
+                } else {
+                    writeln!(out, "  c{} -> c{};", node.card_id, won_id)?;
+                }
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::scan_cards;
+    use indoc::indoc;
+    use num::Zero;
+
+    const INPUT: &str = indoc! {"
+        Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+        Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+        Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+        Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+        Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+        Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+    "};
+
+    fn provenance(input: &str) -> Provenance {
+        let cards = scan_cards(input).unwrap();
+        Provenance::new(&cards, &CopyRule::Next, EndPolicy::Clamp).unwrap()
+    }
+
+    #[test]
+    fn test_provenance() {
+        let provenance = provenance(INPUT);
+        let column = |f: fn(&CardNode) -> String| -> Vec<String> {
+            provenance.nodes().iter().map(f).collect()
+        };
+
+        assert_eq!(
+            column(|node| node.copies.to_string()),
+            vec!["1", "2", "4", "8", "14", "1"]
+        );
+        assert_eq!(
+            column(|node| node.contribution.to_string()),
+            vec!["15", "7", "4", "2", "1", "1"]
+        );
+        assert_eq!(
+            column(|node| node.depth.to_string()),
+            vec!["4", "3", "2", "1", "0", "0"]
+        );
+        assert_eq!(provenance.nodes()[0].wins, vec![2, 3, 4, 5]);
+
+        // Every final card traces back to exactly one original
+        let contributions: BigUint = provenance
+            .nodes()
+            .iter()
+            .map(|node| &node.contribution)
+            .sum();
+        assert_eq!(contributions, provenance.total());
+        assert_eq!(provenance.total(), BigUint::from(30u32));
+    }
+
+    #[test]
+    fn test_longest_chain_and_histogram() {
+        let provenance = provenance(INPUT);
+        assert_eq!(provenance.longest_chain(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            provenance.histogram(),
+            BTreeMap::from([(0, 2), (1, 1), (2, 2), (4, 1)])
+        );
+
+        assert_eq!(Provenance::default().longest_chain(), Vec::<u32>::new());
+        assert_eq!(Provenance::default().total(), BigUint::zero());
+    }
+
+    #[test]
+    fn test_write_json() {
+        let provenance = provenance("Card 1: 1 | 1\nCard 2: 1 | 2");
+        let mut json = Vec::new();
+        provenance.write_json(&mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            indoc! {r#"
+                {
+                  "total": "3",
+                  "cards": [
+                    {"id": 1, "matches": 1, "wins": [2], "copies": "1", "contribution": "2", "depth": 1},
+                    {"id": 2, "matches": 0, "wins": [], "copies": "2", "contribution": "1", "depth": 0}
+                  ],
+                  "longest_chain": [1, 2],
+                  "histogram": {"0": 1, "1": 1}
+                }
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let provenance = provenance("Card 1: 1 2 | 1 2\nCard 2: 1 | 1\nCard 3: 1 | 2");
+        let mut dot = Vec::new();
+        provenance.write_dot(&mut dot).unwrap();
+        assert_eq!(
+            String::from_utf8(dot).unwrap(),
+            indoc! {r#"
+                digraph cards {
+                  c1 [label="Card 1 x1"];
+                  c2 [label="Card 2 x2"];
+                  c3 [label="Card 3 x4"];
+                  c1 -> c2 [style=bold];
+                  c1 -> c3;
+                  c2 -> c3 [style=bold];
+                }
+            "#}
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! How a card's matches turn into points and into copies of other cards.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Points awarded for a number of matches.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Scoring {
+    /// One point for the first match, doubled for each one after (part 1).
+    #[default]
+    Doubling,
+    /// One point per match.
+    Linear,
+    /// `points[m]` for `m` matches; anything past the end of the table scores
+    /// the last entry.
+    Table(Vec<u64>),
+}
+
+impl Scoring {
+    /// Doubling saturates at `u64::MAX` instead of overflowing.
+    pub fn score(&self, matches: u32) -> u64 {
+        match self {
+            Scoring::Doubling if matches == 0 => 0,
+            Scoring::Doubling => 1u64.checked_shl(matches - 1).unwrap_or(u64::MAX),
+            Scoring::Linear => matches as u64,
+            Scoring::Table(points) => {
+                let index = (matches as usize).min(points.len().saturating_sub(1));
+                points.get(index).copied().unwrap_or(0)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Scoring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scoring::Doubling => write!(f, "doubling"),
+            Scoring::Linear => write!(f, "linear"),
+            Scoring::Table(points) => write!(f, "table:{}", join(points)),
+        }
+    }
+}
+
+/// `doubling`, `linear` or `table:0,1,3,6`.
+impl FromStr for Scoring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "doubling" => Ok(Scoring::Doubling),
+            "linear" => Ok(Scoring::Linear),
+            _ => match s.strip_prefix("table:") {
+                Some(points) => Ok(Scoring::Table(parse_list(points)?)),
+                None => Err(format!("unknown scoring {:?}", s)),
+            },
+        }
+    }
+}
+
+/// Which cards a card wins copies of, as offsets from its own id.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CopyRule {
+    /// `m` matches win the next `m` cards (part 2).
+    #[default]
+    Next,
+    /// The `k`th match wins the card `offsets[k - 1]` ids further on; matches
+    /// beyond the list win nothing.
+    Offsets(Vec<u32>),
+}
+
+impl CopyRule {
+    /// Offsets of the cards won with `matches` matches, in ascending order of match.
+    pub fn offsets(&self, matches: u32) -> Vec<u32> {
+        match self {
+            CopyRule::Next => (1..=matches).collect(),
+            CopyRule::Offsets(offsets) => offsets.iter().take(matches as usize).copied().collect(),
+        }
+    }
+}
+
+impl fmt::Display for CopyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyRule::Next => write!(f, "next"),
+            CopyRule::Offsets(offsets) => write!(f, "offsets:{}", join(offsets)),
+        }
+    }
+}
+
+/// `next` or `offsets:1,3,5`.
+impl FromStr for CopyRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "next" {
+            return Ok(CopyRule::Next);
+        }
+        match s.strip_prefix("offsets:") {
+            Some(offsets) => {
+                let offsets: Vec<u32> = parse_list(offsets)?;
+                if offsets.contains(&0) {
+                    return Err("a card can't win a copy of itself".to_string());
+                }
+                Ok(CopyRule::Offsets(offsets))
+            }
+            None => Err(format!("unknown copy rule {:?}", s)),
+        }
+    }
+}
+
+fn parse_list<T: FromStr>(s: &str) -> Result<Vec<T>, String> {
+    s.split(',')
+        .map(|item| {
+            item.trim()
+                .parse::<T>()
+                .map_err(|_| format!("invalid number {:?}", item))
+        })
+        .collect()
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    values.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let scores: Vec<u64> = (0..5).map(|m| Scoring::Doubling.score(m)).collect();
+        assert_eq!(scores, vec![0, 1, 2, 4, 8]);
+        assert_eq!(Scoring::Doubling.score(100), u64::MAX);
+
+        let scores: Vec<u64> = (0..5).map(|m| Scoring::Linear.score(m)).collect();
+        assert_eq!(scores, vec![0, 1, 2, 3, 4]);
+
+        let table = Scoring::Table(vec![0, 1, 3, 6]);
+        let scores: Vec<u64> = (0..6).map(|m| table.score(m)).collect();
+        assert_eq!(scores, vec![0, 1, 3, 6, 6, 6]);
+        assert_eq!(Scoring::Table(vec![]).score(3), 0);
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(CopyRule::Next.offsets(0), Vec::<u32>::new());
+        assert_eq!(CopyRule::Next.offsets(3), vec![1, 2, 3]);
+
+        let rule = CopyRule::Offsets(vec![2, 4]);
+        assert_eq!(rule.offsets(1), vec![2]);
+        assert_eq!(rule.offsets(5), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("doubling".parse(), Ok(Scoring::Doubling));
+        assert_eq!("linear".parse(), Ok(Scoring::Linear));
+        assert_eq!("table:0,1, 3".parse(), Ok(Scoring::Table(vec![0, 1, 3])));
+        assert!("table:1,x".parse::<Scoring>().is_err());
+        assert!("squared".parse::<Scoring>().is_err());
+
+        assert_eq!("next".parse(), Ok(CopyRule::Next));
+        assert_eq!("offsets:1,3".parse(), Ok(CopyRule::Offsets(vec![1, 3])));
+        assert!("offsets:0,1".parse::<CopyRule>().is_err());
+        assert!("all".parse::<CopyRule>().is_err());
+
+        let table = Scoring::Table(vec![0, 2]);
+        assert_eq!(table.to_string().parse(), Ok(table));
+        let rule = CopyRule::Offsets(vec![1, 5]);
+        assert_eq!(rule.to_string().parse(), Ok(rule));
+    }
+}
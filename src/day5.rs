@@ -0,0 +1,898 @@
+pub mod compose;
+pub mod report;
+pub mod validate;
+
+use compose::PiecewiseMap;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapEntry {
+    pub destination: u64,
+    pub source: u64,
+    pub range: u64,
+}
+
+impl MapEntry {
+    /// One past the last source value this entry covers.
+    pub fn source_end(&self) -> u64 {
+        self.source.saturating_add(self.range)
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        value >= self.source && value < self.source_end()
+    }
+
+    /// Whether the source or destination range reaches `u64::MAX`. Its end,
+    /// one past its last value, then doesn't fit in a `u64`, so the
+    /// exclusive-end ranges used throughout can't represent it.
+    pub fn reaches_max(&self) -> bool {
+        self.source.checked_add(self.range).is_none()
+            || self.destination.checked_add(self.range).is_none()
+    }
+}
+
+/// Tables with up to this many entries are scanned in order rather than binary
+/// searched. Consecutive seeds, as in part 2, keep the scan's branches
+/// predictable, and the real almanac's maps have under 50 entries; see
+/// `examples/day5_bench.rs`.
+pub const SCAN_LIMIT: usize = 64;
+
+/// One `x-to-y map:` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    pub from: String,
+    pub to: String,
+    /// As written in the almanac.
+    pub entries: Vec<MapEntry>,
+    /// `entries` sorted by source, with overlaps settled and the gaps filled
+    /// in, so every value below `u64::MAX` has exactly one entry.
+    table: Vec<MapEntry>,
+}
+
+impl Map {
+    pub fn new(from: &str, to: &str, entries: Vec<MapEntry>) -> Map {
+        Map {
+            from: from.to_string(),
+            to: to.to_string(),
+            table: sort_entries(&entries),
+            entries,
+        }
+    }
+
+    pub fn table(&self) -> &[MapEntry] {
+        &self.table
+    }
+
+    /// Same answer as `grow_seed`, from the sorted table: scanned in order up
+    /// to `SCAN_LIMIT` entries, binary searched past that.
+    pub fn get(&self, value: u64) -> u64 {
+        let entry = if self.table.len() <= SCAN_LIMIT {
+            self.table.iter().find(|entry| value < entry.source_end())
+        } else {
+            let i = self
+                .table
+                .partition_point(|entry| entry.source_end() <= value);
+            self.table.get(i)
+        };
+        match entry {
+            Some(entry) => value - entry.source + entry.destination,
+            None => value,
+        }
+    }
+
+    /// Map ascending `values` with a single pass over the table. The results
+    /// are in the same order as `values`, which leaves them unsorted in general.
+    pub fn get_sorted(&self, values: &[u64]) -> Vec<u64> {
+        let mut entries = self.table.iter().peekable();
+        values
+            .iter()
+            .map(|&value| {
+                while entries
+                    .next_if(|entry| entry.source_end() <= value)
+                    .is_some()
+                {}
+                match entries.peek() {
+                    Some(entry) if entry.contains(value) => {
+                        value - entry.source + entry.destination
+                    }
+                    _ => value,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_ranges(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        grow_ranges(ranges, &self.entries)
+    }
+
+    /// Every value this map sends into `ranges`.
+    pub fn preimage(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        preimage_ranges(ranges, &self.entries)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    UnknownCategory(String),
+    /// No sequence of maps leads from `from` to `to`.
+    NoChain {
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::UnknownCategory(name) => write!(f, "no map mentions {:?}", name),
+            ChainError::NoChain { from, to } => {
+                write!(f, "no chain of maps leads from {:?} to {:?}", from, to)
+            }
+        }
+    }
+}
+
+impl Error for ChainError {}
+
+/// A chain of maps from one category to another.
+#[derive(Debug, Clone)]
+pub struct Route<'a> {
+    from: String,
+    maps: Vec<&'a Map>,
+}
+
+impl Route<'_> {
+    pub fn get(&self, value: u64) -> u64 {
+        self.maps.iter().fold(value, |value, map| map.get(value))
+    }
+
+    /// `value` in every category along the route, starting with the first.
+    pub fn path(&self, value: u64) -> Vec<(String, u64)> {
+        let mut path = vec![(self.from.clone(), value)];
+        for map in &self.maps {
+            let value = map.get(path[path.len() - 1].1);
+            path.push((map.to.clone(), value));
+        }
+        path
+    }
+
+    /// Carry many values at once, one merge pass per map. Returns the values
+    /// at the end of the route sorted, not in the order of `values`.
+    pub fn get_all(&self, mut values: Vec<u64>) -> Vec<u64> {
+        values.sort_unstable();
+        for map in &self.maps {
+            // Each table entry hands back an ascending run, which the stable
+            // sort merges rather than sorting from scratch
+            values = map.get_sorted(&values);
+            values.sort();
+        }
+        values
+    }
+}
+
+/// The seed numbers and every map, in the order they appear. The maps form a
+/// graph of categories with an edge for each map, and values are carried from
+/// one category to another along the shortest chain of maps.
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    pub maps: Vec<Map>,
+}
+
+impl Almanac {
+    // Not sure if this is idiomatic Rust, but I liked the idea of passing an iterator back to
+    // the same function so that I can just keep reading from the same thing until its used up
+    pub fn parse(input: &str) -> Almanac {
+        let (seeds, mut lines) = read_seeds(input.lines());
+
+        let mut maps = Vec::new();
+        while lines.clone().any(|line| !line.trim().is_empty()) {
+            let (map, rest) = read_map(lines);
+            maps.push(map);
+            lines = rest;
+        }
+
+        Almanac { seeds, maps }
+    }
+
+    /// Every category named in a header, in order of first appearance.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = Vec::new();
+        for map in &self.maps {
+            for name in [&map.from, &map.to] {
+                if !categories.contains(&name.as_str()) {
+                    categories.push(name);
+                }
+            }
+        }
+        categories
+    }
+
+    /// The shortest sequence of maps taking `from` values to `to` values.
+    /// Going from a category to itself needs no maps at all.
+    pub fn chain(&self, from: &str, to: &str) -> Result<Vec<&Map>, ChainError> {
+        let categories = self.categories();
+        for name in [from, to] {
+            if !categories.contains(&name) {
+                return Err(ChainError::UnknownCategory(name.to_string()));
+            }
+        }
+
+        // Breadth-first search, remembering the map used to reach each category
+        let mut reached_by: HashMap<&str, Option<&Map>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                break;
+            }
+            for map in self.maps.iter().filter(|map| map.from == category) {
+                if !reached_by.contains_key(map.to.as_str()) {
+                    reached_by.insert(&map.to, Some(map));
+                    queue.push_back(&map.to);
+                }
+            }
+        }
+
+        if !reached_by.contains_key(to) {
+            return Err(ChainError::NoChain {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+
+        let mut chain = Vec::new();
+        let mut category = to;
+        while let Some(Some(map)) = reached_by.get(category) {
+            chain.push(*map);
+            category = &map.from;
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// The chain from `from` to `to`, found once to carry many values along.
+    pub fn route(&self, from: &str, to: &str) -> Result<Route<'_>, ChainError> {
+        Ok(Route {
+            from: from.to_string(),
+            maps: self.chain(from, to)?,
+        })
+    }
+
+    /// One value from `from` to `to`. This finds the chain on every call, so
+    /// for more than a handful of values take a `route` first.
+    pub fn convert(&self, from: &str, to: &str, value: u64) -> Result<u64, ChainError> {
+        Ok(self.route(from, to)?.get(value))
+    }
+
+    /// `value` in every category from `from` to `to`, starting with `from`.
+    pub fn path(&self, from: &str, to: &str, value: u64) -> Result<Vec<(String, u64)>, ChainError> {
+        Ok(self.route(from, to)?.path(value))
+    }
+
+    /// Carry many values at once, one merge pass per map. Returns the `to`
+    /// values sorted, not in the order of `values`.
+    pub fn convert_all(
+        &self,
+        from: &str,
+        to: &str,
+        values: Vec<u64>,
+    ) -> Result<Vec<u64>, ChainError> {
+        Ok(self.route(from, to)?.get_all(values))
+    }
+
+    /// Every `to` value reached from the `from` values in `ranges`, as sorted,
+    /// non-overlapping ranges.
+    pub fn convert_ranges(
+        &self,
+        from: &str,
+        to: &str,
+        ranges: &[Range<u64>],
+    ) -> Result<Vec<Range<u64>>, ChainError> {
+        let chain = self.chain(from, to)?;
+        Ok(chain
+            .iter()
+            .fold(merge_ranges(ranges.to_vec()), |values, map| {
+                map.get_ranges(&values)
+            }))
+    }
+
+    /// Every `from` value that ends up in one of the `to` ranges, found by
+    /// running the chain of maps backwards one map at a time. `compose` and
+    /// `PiecewiseMap::preimage` give the same answer in one step.
+    pub fn preimage(
+        &self,
+        from: &str,
+        to: &str,
+        ranges: &[Range<u64>],
+    ) -> Result<Vec<Range<u64>>, ChainError> {
+        let chain = self.chain(from, to)?;
+        Ok(chain
+            .iter()
+            .rev()
+            .fold(merge_ranges(ranges.to_vec()), |values, map| {
+                map.preimage(&values)
+            }))
+    }
+
+    /// The whole chain from `from` to `to` as one function.
+    pub fn compose(&self, from: &str, to: &str) -> Result<PiecewiseMap, ChainError> {
+        let chain = self.chain(from, to)?;
+        Ok(chain
+            .iter()
+            .fold(PiecewiseMap::identity(), |composed, map| {
+                composed.then(&PiecewiseMap::from_entries(&map.entries))
+            }))
+    }
+
+    /// Where a seed ends up. Like `convert`, this finds the chain every time.
+    pub fn location(&self, seed: u64) -> Result<u64, ChainError> {
+        self.convert("seed", "location", seed)
+    }
+
+    /// The seed line read as `start length` pairs, for part 2.
+    pub fn seed_ranges(&self) -> Vec<Range<u64>> {
+        self.seeds
+            .chunks_exact(2)
+            .map(|pair| pair[0]..pair[0].saturating_add(pair[1]))
+            .collect()
+    }
+
+    /// Every location reached from seeds in `ranges`.
+    pub fn location_ranges(&self, ranges: &[Range<u64>]) -> Result<Vec<Range<u64>>, ChainError> {
+        self.convert_ranges("seed", "location", ranges)
+    }
+}
+
+pub fn read_seeds<'a, I>(mut lines: I) -> (Vec<u64>, I)
+where
+    I: Iterator<Item = &'a str>,
+{
+    let seeds: Vec<u64> = lines
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .skip(1)
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+    // Consume the empty line after as well to make my life easier
+    lines.next();
+
+    (seeds, lines)
+}
+
+pub fn read_map<'a, I>(mut lines: I) -> (Map, I)
+where
+    I: Iterator<Item = &'a str>,
+{
+    let header = lines.next().unwrap();
+    let (from, to) = header
+        .trim()
+        .trim_end_matches(':')
+        .trim_end_matches(" map")
+        .split_once("-to-")
+        .expect("map headers look like `x-to-y map:`");
+    let mut map_values: Vec<MapEntry> = Vec::new();
+
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        let entries: Vec<u64> = line
+            .split_whitespace()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        map_values.push(MapEntry {
+            destination: entries[0],
+            source: entries[1],
+            range: entries[2],
+        });
+    }
+
+    (Map::new(from, to, map_values), lines)
+}
+
+/// The first entry covering `seed` decides where it goes; anything no entry
+/// covers keeps its number. Entries that reach `u64::MAX` are skipped, as
+/// everywhere else.
+pub fn grow_seed(seed: u64, map: &[MapEntry]) -> u64 {
+    let mut sprout = seed;
+    for entry in map.iter().filter(|entry| !entry.reaches_max()) {
+        if entry.contains(seed) {
+            sprout = seed - entry.source + entry.destination;
+            break;
+        }
+    }
+
+    sprout
+}
+
+/// The entries `grow_seed` effectively uses: sorted by source, cut back where
+/// a later entry overlaps an earlier one, and with an identity entry for every
+/// gap, so they tile `0..u64::MAX`. Entries that reach `u64::MAX` are dropped, as in
+/// `PiecewiseMap::from_entries`.
+pub fn sort_entries(entries: &[MapEntry]) -> Vec<MapEntry> {
+    PiecewiseMap::from_entries(entries)
+        .segments()
+        .iter()
+        .map(|segment| MapEntry {
+            destination: segment.image().start,
+            source: segment.source.start,
+            range: segment.source.end - segment.source.start,
+        })
+        .collect()
+}
+
+/// Push whole ranges through a map at once, splitting them wherever an entry
+/// starts or stops. Entries are tried in order and each piece is claimed by
+/// the first entry covering it, the same as `grow_seed`, so the result is
+/// exactly the set of values `grow_seed` gives for every value in `ranges`.
+pub fn grow_ranges(ranges: &[Range<u64>], map: &[MapEntry]) -> Vec<Range<u64>> {
+    let mut unmapped: Vec<Range<u64>> = ranges.to_vec();
+    let mut mapped: Vec<Range<u64>> = Vec::new();
+
+    for entry in map.iter().filter(|entry| !entry.reaches_max()) {
+        let mut rest = Vec::new();
+        for range in unmapped {
+            let start = range.start.max(entry.source);
+            let end = range.end.min(entry.source_end());
+            if start >= end {
+                rest.push(range);
+                continue;
+            }
+
+            let shift = |value: u64| value - entry.source + entry.destination;
+            mapped.push(shift(start)..shift(end - 1) + 1);
+            if range.start < start {
+                rest.push(range.start..start);
+            }
+            if end < range.end {
+                rest.push(end..range.end);
+            }
+        }
+        unmapped = rest;
+    }
+
+    mapped.extend(unmapped);
+    merge_ranges(mapped)
+}
+
+/// The reverse of `grow_ranges`: every value that `grow_seed` sends into one
+/// of `ranges`. Values no entry covers map to themselves, so the parts of
+/// `ranges` that fall in those gaps are their own preimage.
+pub fn preimage_ranges(ranges: &[Range<u64>], map: &[MapEntry]) -> Vec<Range<u64>> {
+    let mut unclaimed = vec![compose::DOMAIN];
+    let mut sources: Vec<Range<u64>> = Vec::new();
+    let keep = |claimed: Range<u64>, destination: u64, sources: &mut Vec<Range<u64>>| {
+        // `claimed` source values start at `destination`
+        for range in ranges {
+            let start = range.start.max(destination);
+            let end = range.end.min(destination + (claimed.end - claimed.start));
+            if start < end {
+                let back = |value: u64| value - destination + claimed.start;
+                sources.push(back(start)..back(end));
+            }
+        }
+    };
+
+    for entry in map.iter().filter(|entry| !entry.reaches_max()) {
+        let mut rest = Vec::new();
+        for range in unclaimed {
+            let start = range.start.max(entry.source);
+            let end = range.end.min(entry.source_end());
+            if start >= end {
+                rest.push(range);
+                continue;
+            }
+
+            keep(
+                start..end,
+                start - entry.source + entry.destination,
+                &mut sources,
+            );
+            rest.extend([range.start..start, end..range.end]);
+        }
+        unclaimed = rest.into_iter().filter(|range| !range.is_empty()).collect();
+    }
+    for range in unclaimed {
+        keep(range.clone(), range.start, &mut sources);
+    }
+
+    merge_ranges(sources)
+}
+
+/// Sort ranges and join any that overlap or touch.
+pub fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        seeds: 79 14 55 13
+
+        seed-to-soil map:
+        50 98 2
+        52 50 48
+
+        soil-to-fertilizer map:
+        0 15 37
+        37 52 2
+        39 0 15
+
+        fertilizer-to-water map:
+        49 53 8
+        0 11 42
+        42 0 7
+        57 7 4
+
+        water-to-light map:
+        88 18 7
+        18 25 70
+
+        light-to-temperature map:
+        45 77 23
+        81 45 19
+        68 64 13
+
+        temperature-to-humidity map:
+        0 69 1
+        1 0 69
+
+        humidity-to-location map:
+        60 56 37
+        56 93 4
+    "};
+
+    #[test]
+    fn test_parse() {
+        let almanac = Almanac::parse(INPUT);
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.maps.len(), 7);
+        assert_eq!(almanac.maps[0].from, "seed");
+        assert_eq!(almanac.maps[0].to, "soil");
+        assert_eq!(
+            almanac.maps[0].entries[1],
+            MapEntry {
+                destination: 52,
+                source: 50,
+                range: 48
+            }
+        );
+        assert_eq!(almanac.seed_ranges(), vec![79..93, 55..68]);
+    }
+
+    #[test]
+    fn test_location() {
+        let almanac = Almanac::parse(INPUT);
+        let locations: Vec<u64> = almanac
+            .seeds
+            .iter()
+            .map(|&seed| almanac.location(seed).unwrap())
+            .collect();
+        assert_eq!(locations, vec![82, 43, 86, 35]);
+    }
+
+    #[test]
+    fn test_path() {
+        let almanac = Almanac::parse(INPUT);
+        assert_eq!(
+            almanac.path("seed", "location", 13),
+            Ok([
+                ("seed", 13),
+                ("soil", 13),
+                ("fertilizer", 52),
+                ("water", 41),
+                ("light", 34),
+                ("temperature", 34),
+                ("humidity", 35),
+                ("location", 35)
+            ]
+            .map(|(category, value)| (category.to_string(), value))
+            .to_vec())
+        );
+        assert_eq!(
+            almanac.path("water", "water", 7),
+            Ok(vec![("water".to_string(), 7)])
+        );
+        assert!(almanac.path("seed", "colour", 7).is_err());
+    }
+
+    fn grow(range: Range<u64>, map: &[MapEntry]) -> Vec<Range<u64>> {
+        grow_ranges(std::slice::from_ref(&range), map)
+    }
+
+    #[test]
+    fn test_grow_ranges() {
+        let map = vec![
+            MapEntry {
+                destination: 50,
+                source: 98,
+                range: 2,
+            },
+            MapEntry {
+                destination: 52,
+                source: 50,
+                range: 48,
+            },
+        ];
+        // 45..50 is left alone, 50..98 moves up by 2 and 98..100 drops to 50..52,
+        // which together cover 45..100 again
+        assert_eq!(grow(45..100, &map), vec![45..100]);
+        assert_eq!(grow(96..102, &map), vec![50..52, 98..102]);
+
+        // The first entry wins where two overlap
+        let overlapping = vec![
+            MapEntry {
+                destination: 100,
+                source: 0,
+                range: 5,
+            },
+            MapEntry {
+                destination: 200,
+                source: 3,
+                range: 5,
+            },
+        ];
+        assert_eq!(grow(0..10, &overlapping), vec![8..10, 100..105, 202..205]);
+    }
+
+    #[test]
+    fn test_location_ranges_match_brute_force() {
+        let almanac = Almanac::parse(INPUT);
+        let ranges = almanac.seed_ranges();
+
+        let mut expected: Vec<u64> = ranges
+            .iter()
+            .cloned()
+            .flatten()
+            .map(|seed| almanac.location(seed).unwrap())
+            .collect();
+        expected.sort();
+        expected.dedup();
+
+        let actual: Vec<u64> = almanac
+            .location_ranges(&ranges)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(actual[0], 46);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            merge_ranges(vec![5..8, 0..2, 2..3, 7..10, 4..4]),
+            vec![0..3, 5..10]
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        let almanac = Almanac::parse(INPUT);
+        assert_eq!(
+            almanac.categories(),
+            vec![
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+
+        let chain: Vec<&str> = almanac
+            .chain("soil", "light")
+            .unwrap()
+            .iter()
+            .map(|map| map.to.as_str())
+            .collect();
+        assert_eq!(chain, vec!["fertilizer", "water", "light"]);
+        assert_eq!(almanac.chain("water", "water").unwrap().len(), 0);
+
+        // Seed 79 has soil 81, fertilizer 81, water 81, light 74,
+        // temperature 78, humidity 78 and location 82
+        assert_eq!(almanac.convert("seed", "humidity", 79), Ok(78));
+        assert_eq!(almanac.convert("water", "temperature", 81), Ok(78));
+
+        assert_eq!(
+            almanac.chain("location", "seed").err(),
+            Some(ChainError::NoChain {
+                from: "location".to_string(),
+                to: "seed".to_string()
+            })
+        );
+        assert_eq!(
+            almanac.convert("seed", "sunlight", 79),
+            Err(ChainError::UnknownCategory("sunlight".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_maps_in_any_order() {
+        // Shuffled, with an extra map branching off soil
+        let almanac = Almanac::parse(indoc! {"
+            seeds: 1 2 10
+
+            soil-to-water map:
+            100 0 5
+
+            seed-to-soil map:
+            3 1 2
+
+            water-to-location map:
+            0 100 3
+
+            soil-to-pebble map:
+            7 0 20
+        "});
+
+        assert_eq!(almanac.location(1), Ok(103));
+        assert_eq!(almanac.location(2), Ok(104));
+        assert_eq!(almanac.location(10), Ok(10));
+        assert_eq!(almanac.convert("seed", "pebble", 1), Ok(10));
+        assert_eq!(
+            almanac.location_ranges(&[1..3, 10..12]),
+            Ok(vec![10..12, 103..105])
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let almanac = Almanac::parse(INPUT);
+        let composed = almanac.compose("seed", "location").unwrap();
+        let route = almanac.route("seed", "location").unwrap();
+        for seed in 0..120 {
+            assert_eq!(composed.get(seed), route.get(seed));
+        }
+
+        let minimum = almanac
+            .seed_ranges()
+            .into_iter()
+            .filter_map(|range| composed.min_over(range))
+            .min();
+        assert_eq!(minimum, Some(46));
+    }
+
+    /// Just `range`; clippy takes a literal `vec![a..b]` for a mistake. Shared
+    /// with the tests of the submodules.
+    pub(super) fn single(range: Range<u64>) -> Vec<Range<u64>> {
+        vec![range]
+    }
+
+    #[test]
+    fn test_preimage() {
+        let almanac = Almanac::parse(INPUT);
+        let composed = almanac.compose("seed", "location").unwrap();
+        let route = almanac.route("seed", "location").unwrap();
+
+        // The seeds below 120 that reach each location, found by brute force
+        for location in 0..120 {
+            let expected: Vec<u64> = (0..120)
+                .filter(|&seed| route.get(seed) == location)
+                .collect();
+
+            let preimage = almanac
+                .preimage("seed", "location", &single(location..location + 1))
+                .unwrap();
+            assert_eq!(preimage, composed.preimage(&single(location..location + 1)));
+
+            let seeds: Vec<u64> = preimage
+                .into_iter()
+                .flatten()
+                .take_while(|&seed| seed < 120)
+                .collect();
+            assert_eq!(seeds, expected, "location {}", location);
+        }
+
+        // Identity gaps: seed-to-soil neither covers nor lands in 10..20
+        assert_eq!(
+            almanac.preimage("seed", "soil", &single(10..20)),
+            Ok(single(10..20))
+        );
+        assert_eq!(
+            almanac.preimage("seed", "soil", &single(50..52)),
+            Ok(single(98..100))
+        );
+    }
+
+    #[test]
+    fn test_sorted_table() {
+        let almanac = Almanac::parse(INPUT);
+        let map = &almanac.maps[0];
+        assert_eq!(
+            map.table(),
+            [
+                MapEntry {
+                    destination: 0,
+                    source: 0,
+                    range: 50
+                },
+                MapEntry {
+                    destination: 52,
+                    source: 50,
+                    range: 48
+                },
+                MapEntry {
+                    destination: 50,
+                    source: 98,
+                    range: 2
+                },
+                MapEntry {
+                    destination: 100,
+                    source: 100,
+                    range: u64::MAX - 100
+                },
+            ]
+        );
+
+        let values: Vec<u64> = (0..150).chain([u64::MAX - 1, u64::MAX]).collect();
+        for map in &almanac.maps {
+            let expected: Vec<u64> = values
+                .iter()
+                .map(|&value| grow_seed(value, &map.entries))
+                .collect();
+            let searched: Vec<u64> = values.iter().map(|&value| map.get(value)).collect();
+            assert_eq!(searched, expected);
+            assert_eq!(map.get_sorted(&values), expected);
+        }
+
+        let mut locations: Vec<u64> = almanac
+            .seeds
+            .iter()
+            .map(|&seed| almanac.location(seed).unwrap())
+            .collect();
+        locations.sort();
+        assert_eq!(
+            almanac.convert_all("seed", "location", almanac.seeds.clone()),
+            Ok(locations)
+        );
+    }
+
+    #[test]
+    fn test_entries_reaching_max() {
+        // Both entries end on u64::MAX, so every function leaves them out alike
+        let almanac = Almanac::parse(indoc! {"
+            seeds: 9
+
+            seed-to-location map:
+            18446744073709551606 0 10
+            0 18446744073709551611 5
+        "});
+        let map = &almanac.maps[0];
+        for value in [0, 9, 10, u64::MAX - 1] {
+            assert_eq!(grow_seed(value, &map.entries), value);
+            assert_eq!(map.get(value), value);
+        }
+        assert_eq!(almanac.location(9), Ok(9));
+
+        let ranges = vec![0..10, u64::MAX - 4..u64::MAX];
+        assert_eq!(almanac.location_ranges(&ranges), Ok(ranges.clone()));
+        assert_eq!(
+            almanac.preimage("seed", "location", &ranges),
+            Ok(ranges.clone())
+        );
+    }
+}
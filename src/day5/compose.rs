@@ -0,0 +1,323 @@
+//! A chain of maps collapsed into one piecewise-linear function: sorted,
+//! contiguous segments, each of which shifts its values by a fixed offset.
+
+use super::{merge_ranges, MapEntry};
+use std::io::{self, Write};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub source: Range<u64>,
+    pub offset: i128,
+}
+
+impl Segment {
+    fn shift(&self, value: u64) -> u64 {
+        (value as i128 + self.offset) as u64
+    }
+
+    /// The values this segment's source range lands on.
+    pub fn image(&self) -> Range<u64> {
+        self.shift(self.source.start)..self.shift(self.source.end - 1) + 1
+    }
+}
+
+/// Every value a `PiecewiseMap` has a segment for. `u64::MAX` itself always
+/// maps to itself.
+pub const DOMAIN: Range<u64> = 0..u64::MAX;
+
+/// Its segments cover all of `DOMAIN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    segments: Vec<Segment>,
+}
+
+impl PiecewiseMap {
+    pub fn identity() -> PiecewiseMap {
+        PiecewiseMap {
+            segments: vec![Segment {
+                source: DOMAIN,
+                offset: 0,
+            }],
+        }
+    }
+
+    /// The same function as `grow_seed` over `entries`: the first entry
+    /// covering a value decides where it goes. Entries whose source or
+    /// destination range reaches `u64::MAX` are left out, since a segment
+    /// can't end past it; `validate` reports them.
+    pub fn from_entries(entries: &[MapEntry]) -> PiecewiseMap {
+        let mut unmapped = vec![DOMAIN];
+        let mut segments = Vec::new();
+
+        for entry in entries.iter().filter(|entry| !entry.reaches_max()) {
+            let mut rest = Vec::new();
+            for range in unmapped {
+                let start = range.start.max(entry.source);
+                let end = range.end.min(entry.source_end());
+                if start >= end {
+                    rest.push(range);
+                    continue;
+                }
+
+                segments.push(Segment {
+                    source: start..end,
+                    offset: entry.destination as i128 - entry.source as i128,
+                });
+                rest.extend([range.start..start, end..range.end]);
+            }
+            unmapped = rest.into_iter().filter(|range| !range.is_empty()).collect();
+        }
+
+        segments.extend(
+            unmapped
+                .into_iter()
+                .map(|source| Segment { source, offset: 0 }),
+        );
+        PiecewiseMap::from_segments(segments)
+    }
+
+    /// Sort segments and join neighbours with the same offset.
+    fn from_segments(mut segments: Vec<Segment>) -> PiecewiseMap {
+        segments.sort_by_key(|segment| segment.source.start);
+
+        let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last)
+                    if last.offset == segment.offset && last.source.end == segment.source.start =>
+                {
+                    last.source.end = segment.source.end
+                }
+                _ => merged.push(segment),
+            }
+        }
+        PiecewiseMap { segments: merged }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Index of the segment holding `value`.
+    fn find(&self, value: u64) -> Option<usize> {
+        let i = self
+            .segments
+            .partition_point(|segment| segment.source.start <= value);
+        (i > 0 && value < self.segments[i - 1].source.end).then(|| i - 1)
+    }
+
+    pub fn get(&self, value: u64) -> u64 {
+        match self.find(value) {
+            Some(i) => self.segments[i].shift(value),
+            None => value,
+        }
+    }
+
+    /// The pieces of `range` that fall in each segment, with that segment.
+    fn pieces(&self, range: Range<u64>) -> impl Iterator<Item = (Range<u64>, &Segment)> + '_ {
+        let first = self.find(range.start).unwrap_or(self.segments.len());
+        self.segments[first..]
+            .iter()
+            .take_while(move |segment| segment.source.start < range.end)
+            .map(move |segment| {
+                let start = range.start.max(segment.source.start);
+                let end = range.end.min(segment.source.end);
+                (start..end, segment)
+            })
+            .filter(|(piece, _)| !piece.is_empty())
+    }
+
+    /// Images of `range`, one per segment it crosses, in source order.
+    pub fn get_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        self.pieces(range)
+            .map(|(piece, segment)| segment.shift(piece.start)..segment.shift(piece.end - 1) + 1)
+            .collect()
+    }
+
+    /// The smallest value anything in `range` maps to.
+    pub fn min_over(&self, range: Range<u64>) -> Option<u64> {
+        self.pieces(range)
+            .map(|(piece, segment)| segment.shift(piece.start))
+            .min()
+    }
+
+    /// Every value that lands in one of `ranges`, as sorted, non-overlapping ranges.
+    pub fn preimage(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut sources = Vec::new();
+        for segment in &self.segments {
+            let image = segment.image();
+            for range in ranges {
+                let start = range.start.max(image.start);
+                let end = range.end.min(image.end);
+                if start < end {
+                    sources.push(
+                        (start as i128 - segment.offset) as u64
+                            ..(end as i128 - segment.offset) as u64,
+                    );
+                }
+            }
+        }
+        merge_ranges(sources)
+    }
+
+    /// `self` followed by `next`.
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut segments = Vec::new();
+        for segment in &self.segments {
+            let image = segment.image();
+            for (piece, after) in next.pieces(image) {
+                let start = (piece.start as i128 - segment.offset) as u64;
+                let end = (piece.end as i128 - segment.offset) as u64;
+                segments.push(Segment {
+                    source: start..end,
+                    offset: segment.offset + after.offset,
+                });
+            }
+        }
+        PiecewiseMap::from_segments(segments)
+    }
+
+    /// One `start..end offset` line per segment, identity segments included.
+    pub fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for segment in &self.segments {
+            writeln!(
+                out,
+                "{}..{} {:+}",
+                segment.source.start, segment.source.end, segment.offset
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "[")?;
+        for (i, segment) in self.segments.iter().enumerate() {
+            write!(
+                out,
+                "  {{\"start\": {}, \"end\": {}, \"offset\": {}}}",
+                segment.source.start, segment.source.end, segment.offset
+            )?;
+            writeln!(
+                out,
+                "{}",
+                if i + 1 < self.segments.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(out, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day5::grow_seed;
+    use crate::day5::tests::single;
+
+    fn entry(destination: u64, source: u64, range: u64) -> MapEntry {
+        MapEntry {
+            destination,
+            source,
+            range,
+        }
+    }
+
+    #[test]
+    fn test_from_entries() {
+        let map = PiecewiseMap::from_entries(&[entry(50, 98, 2), entry(52, 50, 48)]);
+        let segments: Vec<(Range<u64>, i128)> = map
+            .segments()
+            .iter()
+            .map(|segment| (segment.source.clone(), segment.offset))
+            .collect();
+        assert_eq!(
+            segments,
+            vec![(0..50, 0), (50..98, 2), (98..100, -48), (100..u64::MAX, 0)]
+        );
+
+        for value in [0, 49, 50, 97, 98, 99, 100, u64::MAX] {
+            assert_eq!(
+                map.get(value),
+                grow_seed(value, &[entry(50, 98, 2), entry(52, 50, 48)])
+            );
+        }
+    }
+
+    #[test]
+    fn test_overlapping_entries() {
+        // The first entry wins where two overlap, as in `grow_seed`
+        let entries = [entry(100, 0, 5), entry(200, 3, 5)];
+        let map = PiecewiseMap::from_entries(&entries);
+        for value in 0..10 {
+            assert_eq!(map.get(value), grow_seed(value, &entries));
+        }
+    }
+
+    #[test]
+    fn test_then() {
+        let first = [entry(10, 0, 10)];
+        let second = [entry(0, 15, 5), entry(100, 5, 3)];
+        let composed =
+            PiecewiseMap::from_entries(&first).then(&PiecewiseMap::from_entries(&second));
+
+        for value in 0..30 {
+            assert_eq!(
+                composed.get(value),
+                grow_seed(grow_seed(value, &first), &second),
+                "value {}",
+                value
+            );
+        }
+        assert_eq!(PiecewiseMap::identity().then(&composed), composed);
+    }
+
+    #[test]
+    fn test_range_queries() {
+        let map = PiecewiseMap::from_entries(&[entry(50, 98, 2), entry(52, 50, 48)]);
+        assert_eq!(map.get_range(45..100), vec![45..50, 52..100, 50..52]);
+        assert_eq!(map.get_range(60..61), vec![62..63]);
+        assert_eq!(map.min_over(45..100), Some(45));
+        assert_eq!(map.min_over(60..100), Some(50));
+        assert_eq!(map.min_over(60..60), None);
+    }
+
+    #[test]
+    fn test_preimage() {
+        let entries = [entry(50, 98, 2), entry(52, 50, 48)];
+        let map = PiecewiseMap::from_entries(&entries);
+
+        // 48 and 49 are left alone and 50 and 51 come from 98 and 99; 50 and
+        // 51 themselves move up to 52 and 53
+        assert_eq!(map.preimage(&single(48..52)), vec![48..50, 98..100]);
+        assert_eq!(map.preimage(&[100..101, 52..53]), vec![50..51, 100..101]);
+
+        for target in 40..110 {
+            let expected: Vec<u64> = (0..200)
+                .filter(|&value| grow_seed(value, &entries) == target)
+                .collect();
+            let actual: Vec<u64> = map
+                .preimage(&single(target..target + 1))
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(actual, expected, "target {}", target);
+        }
+    }
+
+    #[test]
+    fn test_write_text() {
+        let map = PiecewiseMap::from_entries(&[entry(0, 5, 5)]);
+        let mut out = Vec::new();
+        map.write_text(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0..5 +0\n5..10 -5\n10..18446744073709551615 +0\n"
+        );
+
+        let mut out = Vec::new();
+        map.write_json(&mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("{\"start\": 5, \"end\": 10, \"offset\": -5},"));
+    }
+}
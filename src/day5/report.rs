@@ -0,0 +1,206 @@
+//! The seeds behind the lowest locations, and the value each one takes in every
+//! category on the way there.
+
+use super::{Almanac, ChainError, Route};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+/// One seed followed from `seed` to `location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedPath {
+    pub seed: u64,
+    /// The seed range it was taken from, for part 2.
+    pub range: Option<Range<u64>>,
+    /// Each category with this seed's value in it, `seed` first.
+    pub steps: Vec<(String, u64)>,
+}
+
+impl SeedPath {
+    fn new(route: &Route, seed: u64, range: Option<Range<u64>>) -> SeedPath {
+        SeedPath {
+            seed,
+            range,
+            steps: route.path(seed),
+        }
+    }
+
+    pub fn location(&self) -> u64 {
+        self.steps[self.steps.len() - 1].1
+    }
+}
+
+/// `seed 13 -> soil 13 -> ... -> location 35`.
+impl fmt::Display for SeedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|(category, value)| format!("{} {}", category, value))
+            .collect();
+        write!(f, "{}", steps.join(" -> "))
+    }
+}
+
+/// The `top` seeds on the seed line with the lowest locations, lowest first.
+/// Ties go to the lower seed, and a seed listed twice only counts once.
+pub fn best_seeds(almanac: &Almanac, top: usize) -> Result<Vec<SeedPath>, ChainError> {
+    let composed = almanac.compose("seed", "location")?;
+    let route = almanac.route("seed", "location")?;
+    let mut seeds: Vec<(u64, u64)> = almanac
+        .seeds
+        .iter()
+        .map(|&seed| (composed.get(seed), seed))
+        .collect();
+    seeds.sort_unstable();
+    seeds.dedup();
+
+    Ok(seeds
+        .into_iter()
+        .take(top)
+        .map(|(_, seed)| SeedPath::new(&route, seed, None))
+        .collect())
+}
+
+/// The `top` seeds from the part 2 seed ranges with the lowest locations,
+/// lowest first, each with the first range holding it.
+///
+/// Within a piece of a range that one segment of the composed map covers, the
+/// locations climb one at a time from the piece's first seed. So the pieces
+/// are merged like sorted lists, never looking at more than `top` seeds past
+/// their starts.
+pub fn best_seeds_in_ranges(almanac: &Almanac, top: usize) -> Result<Vec<SeedPath>, ChainError> {
+    let composed = almanac.compose("seed", "location")?;
+    let route = almanac.route("seed", "location")?;
+    let ranges = almanac.seed_ranges();
+
+    let mut heap = BinaryHeap::new();
+    for (i, range) in ranges.iter().enumerate() {
+        for segment in composed.segments() {
+            let start = range.start.max(segment.source.start);
+            let end = range.end.min(segment.source.end);
+            if start < end {
+                heap.push(Reverse((composed.get(start), start, i, end)));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut best = Vec::new();
+    while best.len() < top {
+        let Some(Reverse((location, seed, i, end))) = heap.pop() else {
+            break;
+        };
+        if seed + 1 < end {
+            heap.push(Reverse((location + 1, seed + 1, i, end)));
+        }
+        if seen.insert(seed) {
+            best.push(SeedPath::new(&route, seed, Some(ranges[i].clone())));
+        }
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        seeds: 79 14 55 13
+
+        seed-to-soil map:
+        50 98 2
+        52 50 48
+
+        soil-to-fertilizer map:
+        0 15 37
+        37 52 2
+        39 0 15
+
+        fertilizer-to-water map:
+        49 53 8
+        0 11 42
+        42 0 7
+        57 7 4
+
+        water-to-light map:
+        88 18 7
+        18 25 70
+
+        light-to-temperature map:
+        45 77 23
+        81 45 19
+        68 64 13
+
+        temperature-to-humidity map:
+        0 69 1
+        1 0 69
+
+        humidity-to-location map:
+        60 56 37
+        56 93 4
+    "};
+
+    #[test]
+    fn test_best_seeds() {
+        let almanac = Almanac::parse(INPUT);
+        let best = best_seeds(&almanac, 2).unwrap();
+        assert_eq!(
+            best.iter()
+                .map(|path| (path.seed, path.location()))
+                .collect::<Vec<_>>(),
+            vec![(13, 35), (14, 43)]
+        );
+        assert_eq!(best[0].range, None);
+        assert_eq!(
+            best[0].to_string(),
+            "seed 13 -> soil 13 -> fertilizer 52 -> water 41 -> light 34 -> temperature 34 -> humidity 35 -> location 35"
+        );
+        assert_eq!(best_seeds(&almanac, 10).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_best_seeds_in_ranges() {
+        let almanac = Almanac::parse(INPUT);
+        let best = best_seeds_in_ranges(&almanac, 1).unwrap();
+        assert_eq!(best[0].seed, 82);
+        assert_eq!(best[0].range, Some(79..93));
+        assert_eq!(best[0].location(), 46);
+        assert_eq!(best[0].steps[4], ("light".to_string(), 77));
+    }
+
+    #[test]
+    fn test_best_seeds_in_ranges_match_brute_force() {
+        let almanac = Almanac::parse(INPUT);
+        let route = almanac.route("seed", "location").unwrap();
+        let mut expected: Vec<(u64, u64)> = almanac
+            .seed_ranges()
+            .into_iter()
+            .flatten()
+            .map(|seed| (route.get(seed), seed))
+            .collect();
+        expected.sort_unstable();
+
+        let actual: Vec<(u64, u64)> = best_seeds_in_ranges(&almanac, 100)
+            .unwrap()
+            .iter()
+            .map(|path| (path.location(), path.seed))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_overlapping_seed_ranges() {
+        let almanac = Almanac::parse("seeds: 10 5 12 5\n\nseed-to-location map:\n0 10 20\n");
+        let best = best_seeds_in_ranges(&almanac, 3).unwrap();
+        assert_eq!(
+            best.iter()
+                .map(|path| (path.seed, path.range.clone()))
+                .collect::<Vec<_>>(),
+            vec![(10, Some(10..15)), (11, Some(10..15)), (12, Some(10..15))]
+        );
+        assert_eq!(best_seeds_in_ranges(&almanac, 100).unwrap().len(), 7);
+    }
+}
@@ -0,0 +1,331 @@
+//! Checks for almanac maps that `grow_seed` would quietly make sense of.
+//!
+//! In `Strict` mode the first problem is an error. In `Lenient` mode problems
+//! are returned as warnings and the almanac is used as written, with the first
+//! entry covering a value taking it. Entries that reach `u64::MAX` can't be
+//! used either way, so they are always an error.
+
+use super::compose::{PiecewiseMap, DOMAIN};
+use super::{merge_ranges, Almanac, Map};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+impl FromStr for ValidationMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<ValidationMode, String> {
+        match input {
+            "strict" => Ok(ValidationMode::Strict),
+            "lenient" => Ok(ValidationMode::Lenient),
+            _ => Err(format!("unknown validation mode {:?}", input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    ZeroLength,
+    /// Same source range as an earlier entry.
+    Duplicate {
+        of: usize,
+    },
+    /// Shares `values` with the source range of an earlier entry.
+    Overlap {
+        with: usize,
+        values: Range<u64>,
+    },
+    /// The source or destination range reaches `u64::MAX`, so its end doesn't
+    /// fit in a `u64`.
+    ReachesMax,
+    /// Some values are reached from more than one source, or from none.
+    NotBijective {
+        collisions: Vec<Range<u64>>,
+        missed: Vec<Range<u64>>,
+    },
+}
+
+/// A problem with one map, and with one of its entries if `entry` is set.
+/// Entries are numbered from 1 in the order they are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub map: String,
+    pub entry: Option<usize>,
+    pub kind: IssueKind,
+}
+
+impl Issue {
+    /// Whether this stops the almanac being used even in `Lenient` mode.
+    pub fn is_fatal(&self) -> bool {
+        self.kind == IssueKind::ReachesMax
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} map", self.map)?;
+        if let Some(entry) = self.entry {
+            write!(f, ", entry {}", entry)?;
+        }
+        write!(f, ": ")?;
+
+        match &self.kind {
+            IssueKind::ZeroLength => write!(f, "covers no values"),
+            IssueKind::Duplicate { of } => write!(f, "repeats the source range of entry {}", of),
+            IssueKind::Overlap { with, values } => write!(
+                f,
+                "overlaps entry {} on {}..{}",
+                with, values.start, values.end
+            ),
+            IssueKind::ReachesMax => write!(
+                f,
+                "reaches {}, which exclusive-end ranges can't represent",
+                u64::MAX
+            ),
+            IssueKind::NotBijective { collisions, missed } => {
+                write!(f, "is not a bijection")?;
+                if let Some(range) = collisions.first() {
+                    write!(
+                        f,
+                        "; {} ranges are reached twice, starting with {}..{}",
+                        collisions.len(),
+                        range.start,
+                        range.end
+                    )?;
+                }
+                if let Some(range) = missed.first() {
+                    write!(
+                        f,
+                        "; {} ranges are never reached, starting with {}..{}",
+                        missed.len(),
+                        range.start,
+                        range.end
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for Issue {}
+
+/// Every problem with every map, map by map and entry by entry.
+pub fn find_issues(almanac: &Almanac) -> Vec<Issue> {
+    almanac.maps.iter().flat_map(map_issues).collect()
+}
+
+/// `Ok` with the warnings to show, or the problem that rules the almanac out.
+pub fn validate(almanac: &Almanac, mode: ValidationMode) -> Result<Vec<Issue>, Issue> {
+    let issues = find_issues(almanac);
+    let fatal = |issue: &Issue| mode == ValidationMode::Strict || issue.is_fatal();
+    match issues.iter().find(|issue| fatal(issue)) {
+        Some(issue) => Err(issue.clone()),
+        None => Ok(issues),
+    }
+}
+
+fn map_issues(map: &Map) -> Vec<Issue> {
+    let name = format!("{}-to-{}", map.from, map.to);
+    let issue = |entry: Option<usize>, kind: IssueKind| Issue {
+        map: name.clone(),
+        entry,
+        kind,
+    };
+
+    let mut issues = Vec::new();
+    for (i, entry) in map.entries.iter().enumerate() {
+        let number = Some(i + 1);
+        if entry.range == 0 {
+            issues.push(issue(number, IssueKind::ZeroLength));
+            continue;
+        }
+        if entry.reaches_max() {
+            issues.push(issue(number, IssueKind::ReachesMax));
+        }
+
+        for (j, earlier) in map.entries[..i].iter().enumerate() {
+            if earlier.source == entry.source && earlier.range == entry.range {
+                issues.push(issue(number, IssueKind::Duplicate { of: j + 1 }));
+                continue;
+            }
+            let start = entry.source.max(earlier.source);
+            let end = entry.source_end().min(earlier.source_end());
+            if start < end {
+                let kind = IssueKind::Overlap {
+                    with: j + 1,
+                    values: start..end,
+                };
+                issues.push(issue(number, kind));
+            }
+        }
+    }
+
+    // Images are only safe to work out once nothing reaches `u64::MAX`
+    if issues.iter().all(|issue| !issue.is_fatal()) {
+        let (collisions, missed) = coverage(map);
+        if !collisions.is_empty() || !missed.is_empty() {
+            issues.push(issue(None, IssueKind::NotBijective { collisions, missed }));
+        }
+    }
+
+    issues
+}
+
+/// Values below `u64::MAX` that the map reaches more than once, and values it
+/// never reaches.
+fn coverage(map: &Map) -> (Vec<Range<u64>>, Vec<Range<u64>>) {
+    let mut images: Vec<Range<u64>> = PiecewiseMap::from_entries(&map.entries)
+        .segments()
+        .iter()
+        .map(|segment| segment.image())
+        .collect();
+    images.sort_by_key(|image| image.start);
+
+    let mut collisions = Vec::new();
+    let mut missed = Vec::new();
+    let mut covered = DOMAIN.start;
+    for image in images {
+        if image.start < covered {
+            collisions.push(image.start..image.end.min(covered));
+        } else if image.start > covered {
+            missed.push(covered..image.start);
+        }
+        covered = covered.max(image.end);
+    }
+    if covered < DOMAIN.end {
+        missed.push(covered..DOMAIN.end);
+    }
+
+    (merge_ranges(collisions), missed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn almanac(map: &str) -> Almanac {
+        Almanac::parse(&format!("seeds: 1\n\nseed-to-soil map:\n{}", map))
+    }
+
+    fn kinds(almanac: &Almanac) -> Vec<(Option<usize>, IssueKind)> {
+        find_issues(almanac)
+            .into_iter()
+            .map(|issue| (issue.entry, issue.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        // Dropping 98..100 to 50..52 and moving 50..98 up by 2 is a bijection
+        let almanac = almanac("50 98 2\n52 50 48\n");
+        assert_eq!(find_issues(&almanac), vec![]);
+        assert_eq!(validate(&almanac, ValidationMode::Strict), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_zero_length() {
+        let almanac = almanac("50 98 2\n52 50 48\n7 3 0\n");
+        assert_eq!(kinds(&almanac), vec![(Some(3), IssueKind::ZeroLength)]);
+    }
+
+    #[test]
+    fn test_overlap_and_duplicate() {
+        let almanac = almanac(indoc! {"
+            100 0 10
+            200 5 10
+            300 0 10
+        "});
+
+        assert_eq!(
+            kinds(&almanac)[..3],
+            [
+                (
+                    Some(2),
+                    IssueKind::Overlap {
+                        with: 1,
+                        values: 5..10
+                    }
+                ),
+                (Some(3), IssueKind::Duplicate { of: 1 }),
+                (
+                    Some(3),
+                    IssueKind::Overlap {
+                        with: 2,
+                        values: 5..10
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_bijective() {
+        // 0..5 and 5..10 both land on 20..25, and nothing reaches 0..10
+        let almanac = almanac("20 0 5\n20 5 5\n");
+        let issues = find_issues(&almanac);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].entry, None);
+        let IssueKind::NotBijective { collisions, missed } = &issues[0].kind else {
+            panic!("expected a bijection problem, got {:?}", issues[0]);
+        };
+        assert_eq!((collisions.len(), &collisions[0]), (1, &(20..25)));
+        assert_eq!((missed.len(), &missed[0]), (1, &(0..10)));
+        assert_eq!(
+            find_issues(&almanac)[0].to_string(),
+            "seed-to-soil map: is not a bijection; 1 ranges are reached twice, starting with 20..25; 1 ranges are never reached, starting with 0..10"
+        );
+    }
+
+    #[test]
+    fn test_reaches_max() {
+        let almanac = almanac("18446744073709551610 0 10\n");
+        assert_eq!(kinds(&almanac), vec![(Some(1), IssueKind::ReachesMax)]);
+
+        let error = validate(&almanac, ValidationMode::Lenient).unwrap_err();
+        assert!(error.is_fatal());
+        assert_eq!(
+            error.to_string(),
+            "seed-to-soil map, entry 1: reaches 18446744073709551615, which exclusive-end ranges can't represent"
+        );
+    }
+
+    #[test]
+    fn test_destination_at_the_limit() {
+        // 0..10 lands on 18446744073709551605..u64::MAX, the last range whose
+        // end still fits
+        let fits = almanac("18446744073709551605 0 10\n");
+        assert!(validate(&fits, ValidationMode::Lenient).is_ok());
+        assert_eq!(fits.maps[0].get(9), u64::MAX - 1);
+
+        // One further and 9 lands on u64::MAX itself, so the range would have
+        // to end at u64::MAX + 1
+        let past = almanac("18446744073709551606 0 10\n");
+        assert_eq!(kinds(&past), vec![(Some(1), IssueKind::ReachesMax)]);
+        assert!(validate(&past, ValidationMode::Lenient).is_err());
+        // Left out of the table rather than wrapped around
+        assert_eq!(past.maps[0].get(9), 9);
+    }
+
+    #[test]
+    fn test_modes() {
+        let almanac = almanac("20 0 5\n20 5 5\n");
+        assert!(validate(&almanac, ValidationMode::Strict).is_err());
+        assert_eq!(
+            validate(&almanac, ValidationMode::Lenient).map(|warnings| warnings.len()),
+            Ok(1)
+        );
+
+        assert_eq!("lenient".parse(), Ok(ValidationMode::Lenient));
+        assert!("loose".parse::<ValidationMode>().is_err());
+    }
+}
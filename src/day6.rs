@@ -0,0 +1,211 @@
+pub mod physics;
+
+use num::{BigUint, One, Zero};
+use std::ops::Range;
+
+/// Times and distances are big integers, so records with as many digits as
+/// the kerning-free reading gives still fit, and so does every hold's distance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaceRecord {
+    pub time: BigUint,
+    pub distance: BigUint,
+}
+
+impl RaceRecord {
+    pub fn new<T: Into<BigUint>>(time: T, distance: T) -> RaceRecord {
+        RaceRecord {
+            time: time.into(),
+            distance: distance.into(),
+        }
+    }
+
+    /// How far the boat goes after holding the button for `hold` milliseconds.
+    pub fn travel_distance(&self, hold: &BigUint) -> BigUint {
+        hold * (&self.time - hold)
+    }
+
+    /// Every hold that beats the record.
+    ///
+    /// `hold * (time - hold) > distance` is the same as
+    /// `(time - 2 * hold)^2 < time^2 - 4 * distance`, so with `m` the largest
+    /// integer whose square is under the right-hand side, `2 * hold` runs from
+    /// `time - m` to `time + m`. Everything stays in integers, so long races
+    /// don't suffer from floating-point rounding.
+    pub fn winning_holds(&self) -> Range<BigUint> {
+        let square = &self.time * &self.time;
+        let record = &self.distance * 4u32;
+        if square <= record {
+            return BigUint::zero()..BigUint::zero();
+        }
+        let m = (square - record - 1u32).sqrt();
+
+        // With `time` and `m` of different parity both ends round inwards,
+        // which can leave nothing in between
+        let first = (&self.time - &m + 1u32) / 2u32;
+        let end = (&self.time + &m) / 2u32 + BigUint::one();
+        if first >= end {
+            return BigUint::zero()..BigUint::zero();
+        }
+        first..end
+    }
+
+    /// How many holds beat the record.
+    pub fn ways_to_win(&self) -> BigUint {
+        let holds = self.winning_holds();
+        holds.end - holds.start
+    }
+}
+
+/// One race per column, for part 1.
+pub fn read_races(input: &str) -> Vec<RaceRecord> {
+    let lines: Vec<&str> = input.lines().collect();
+    assert!(lines.len() == 2);
+
+    let all_times: Vec<BigUint> = lines[0]
+        .split_whitespace()
+        .skip(1)
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+    let all_distances: Vec<BigUint> = lines[1]
+        .split_whitespace()
+        .skip(1)
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+    assert!(all_times.len() == all_distances.len());
+
+    all_times
+        .into_iter()
+        .zip(all_distances)
+        .map(|(time, distance)| RaceRecord::new(time, distance))
+        .collect()
+}
+
+/// One race with the spaces between digits ignored, for part 2.
+pub fn read_race(input: &str) -> RaceRecord {
+    let lines: Vec<&str> = input.lines().collect();
+    assert!(lines.len() == 2);
+
+    let numbers: String = lines[0].split_whitespace().skip(1).collect();
+    let time: BigUint = numbers.parse().unwrap();
+
+    let numbers: String = lines[1].split_whitespace().skip(1).collect();
+    let distance: BigUint = numbers.parse().unwrap();
+
+    RaceRecord::new(time, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        Time:      7  15   30
+        Distance:  9  40  200
+    "};
+
+    fn range(start: u64, end: u64) -> Range<BigUint> {
+        start.into()..end.into()
+    }
+
+    /// Tries every hold.
+    fn brute_force(time: u64, distance: u64) -> Vec<u64> {
+        let record = RaceRecord::new(time, distance);
+        (0..=time)
+            .filter(|&hold| record.travel_distance(&hold.into()) > record.distance)
+            .collect()
+    }
+
+    #[test]
+    fn test_read_races() {
+        let races = read_races(INPUT);
+        assert_eq!(
+            races,
+            vec![
+                RaceRecord::new(7u32, 9),
+                RaceRecord::new(15u32, 40),
+                RaceRecord::new(30u32, 200)
+            ]
+        );
+        assert_eq!(read_race(INPUT), RaceRecord::new(71530u32, 940200));
+    }
+
+    #[test]
+    fn test_read_long_race() {
+        // Too many digits for a u64
+        let race = read_race(
+            "Time: 4000000000 0000000000
+Distance: 1 0000000000 0000000000 0000000000
+",
+        );
+        assert_eq!(race.time, "40000000000000000000".parse().unwrap());
+        assert_eq!(race.ways_to_win(), "39999999949999999969".parse().unwrap());
+    }
+
+    #[test]
+    fn test_travel_distance() {
+        let record = RaceRecord::new(7u32, 9);
+        let distances: Vec<BigUint> = (0..=7u32)
+            .map(|hold| record.travel_distance(&hold.into()))
+            .collect();
+        assert_eq!(
+            distances,
+            [0u32, 6, 10, 12, 12, 10, 6, 0].map(BigUint::from).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_winning_holds() {
+        assert_eq!(RaceRecord::new(7u32, 9).winning_holds(), range(2, 6));
+        assert_eq!(RaceRecord::new(15u32, 40).winning_holds(), range(4, 12));
+        // 10 * 20 only ties the record
+        assert_eq!(RaceRecord::new(30u32, 200).winning_holds(), range(11, 20));
+        assert_eq!(
+            RaceRecord::new(71530u32, 940200).ways_to_win(),
+            BigUint::from(71503u32)
+        );
+    }
+
+    #[test]
+    fn test_winning_holds_match_brute_force() {
+        for time in 0..60 {
+            for distance in 0..time * time / 4 + 2 {
+                let record = RaceRecord::new(time, distance);
+                let expected = brute_force(time, distance);
+                let holds = match (expected.first(), expected.last()) {
+                    (Some(&first), Some(&last)) => range(first, last + 1),
+                    _ => range(0, 0),
+                };
+                assert_eq!(record.winning_holds(), holds, "{:?}", record);
+                assert_eq!(record.ways_to_win(), BigUint::from(expected.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_way_to_win() {
+        assert!(RaceRecord::new(0u32, 0).ways_to_win().is_zero());
+        assert!(RaceRecord::new(4u32, 4).ways_to_win().is_zero());
+        assert_eq!(RaceRecord::new(3u32, 100).winning_holds(), range(0, 0));
+    }
+
+    #[test]
+    fn test_long_race() {
+        // Far past u64, let alone the 2^53 where an f64 square root rounds
+        let time: BigUint = "1000000000000000000000000000007".parse().unwrap();
+        let distance: BigUint = "249999999999999999999999999999999999999999999999999999999"
+            .parse()
+            .unwrap();
+        let record = RaceRecord::new(time, distance);
+        let holds = record.winning_holds();
+        let one = BigUint::one();
+
+        let beats = |hold: &BigUint| record.travel_distance(hold) > record.distance;
+        assert!(beats(&holds.start));
+        assert!(!beats(&(&holds.start - &one)));
+        assert!(beats(&(&holds.end - &one)));
+        assert!(!beats(&holds.end));
+    }
+}
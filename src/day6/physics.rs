@@ -0,0 +1,565 @@
+//! Boats other than the standard one: speed as a polynomial in the time the
+//! button is held, an optional top speed, and a delay after letting go before
+//! the boat moves.
+//!
+//! The standard boat and plain acceleration are solved in closed form. Anything
+//! else is solved exactly by search: distance minus the record is a polynomial
+//! in the hold, its forward difference is one of lower degree, and the runs
+//! where that difference is positive split the holds into pieces on which the
+//! distance only rises or only falls, each of which a binary search settles.
+
+use super::RaceRecord;
+use num::{BigInt, BigUint, One, Signed, Zero};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoatModel {
+    /// Speed after holding the button for `h` milliseconds is
+    /// `speed[0] + speed[1] * h + speed[2] * h^2 + ...`. No trailing zeros.
+    pub speed: Vec<BigUint>,
+    pub max_speed: Option<BigUint>,
+    /// Milliseconds the boat sits still after the button is let go.
+    pub penalty: BigUint,
+}
+
+/// The standard boat: one millimetre per millisecond of speed for every
+/// millisecond held.
+impl Default for BoatModel {
+    fn default() -> BoatModel {
+        BoatModel::accelerating(BigUint::one())
+    }
+}
+
+impl BoatModel {
+    /// Speed goes up by `acceleration` for every millisecond held.
+    pub fn accelerating(acceleration: BigUint) -> BoatModel {
+        BoatModel::with_speed(vec![BigUint::zero(), acceleration])
+    }
+
+    pub fn with_speed(mut speed: Vec<BigUint>) -> BoatModel {
+        while speed.last().is_some_and(Zero::is_zero) {
+            speed.pop();
+        }
+        BoatModel {
+            speed,
+            max_speed: None,
+            penalty: BigUint::zero(),
+        }
+    }
+
+    /// Speed after holding for `hold` milliseconds.
+    pub fn speed(&self, hold: &BigUint) -> BigUint {
+        let speed = self
+            .speed
+            .iter()
+            .rev()
+            .fold(BigUint::zero(), |speed, coefficient| {
+                speed * hold + coefficient
+            });
+        match &self.max_speed {
+            Some(max_speed) if speed > *max_speed => max_speed.clone(),
+            _ => speed,
+        }
+    }
+
+    /// How far the boat goes in a race of `time` milliseconds after holding
+    /// the button for `hold` of them.
+    pub fn travel_distance(&self, time: &BigUint, hold: &BigUint) -> BigUint {
+        let used = hold + &self.penalty;
+        if used >= *time {
+            return BigUint::zero();
+        }
+        self.speed(hold) * (time - used)
+    }
+
+    /// Every hold that beats `record`, as sorted, non-overlapping ranges.
+    /// The speed never drops, but the distance `speed(h) * (time - h)` can
+    /// rise and fall more than once, and then there may be several.
+    pub fn winning_holds(&self, record: &RaceRecord) -> Vec<Range<BigUint>> {
+        if record.time <= self.penalty {
+            return Vec::new();
+        }
+        // Holding past `moving` leaves no time to move in
+        let moving = &record.time - &self.penalty;
+
+        if let Some(holds) = self.closed_form(&moving, &record.distance) {
+            return holds;
+        }
+
+        let moving = BigInt::from(moving);
+        let distance = BigInt::from(record.distance.clone());
+        let speed: Vec<BigInt> = self.speed.iter().cloned().map(BigInt::from).collect();
+        // `moving - h`, the time left to move in after holding for `h`
+        let time_left = vec![moving.clone(), -BigInt::one()];
+        let end = &moving + 1;
+
+        // With no negative coefficients the speed never drops, so from the
+        // first hold that reaches the top speed on it stays there
+        let (at, max_speed) = match &self.max_speed {
+            Some(max_speed) => {
+                let max_speed = BigInt::from(max_speed.clone());
+                let at = first_at_least(&speed, &max_speed, &end).unwrap_or_else(|| end.clone());
+                (at, max_speed)
+            }
+            None => (end.clone(), BigInt::zero()),
+        };
+
+        let rising = sub(&mul(&speed, &time_left), std::slice::from_ref(&distance));
+        let flat = sub(&mul(&[max_speed], &time_left), &[distance]);
+        let mut holds = positive_runs(&rising, &BigInt::zero(), &at);
+        holds.extend(positive_runs(&flat, &at, &end));
+
+        merge(holds)
+            .into_iter()
+            .map(|range| to_unsigned(range.start)..to_unsigned(range.end))
+            .collect()
+    }
+
+    /// How many holds beat `record`.
+    pub fn ways_to_win(&self, record: &RaceRecord) -> BigUint {
+        count_holds(&self.winning_holds(record))
+    }
+
+    /// `a * h * (moving - h) > distance` is `h * (moving - h) > distance / a`
+    /// rounded down, which is the standard race over `moving` milliseconds.
+    fn closed_form(&self, moving: &BigUint, distance: &BigUint) -> Option<Vec<Range<BigUint>>> {
+        if self.max_speed.is_some() {
+            return None;
+        }
+        match self.speed.as_slice() {
+            [] => Some(Vec::new()),
+            [constant, acceleration] if constant.is_zero() => {
+                let standard = RaceRecord::new(moving.clone(), distance / acceleration);
+                let holds = standard.winning_holds();
+                Some(if holds.is_empty() {
+                    vec![]
+                } else {
+                    vec![holds]
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BoatModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        for (power, coefficient) in self.speed.iter().enumerate() {
+            let coefficient = match coefficient.to_string().as_str() {
+                "0" => continue,
+                "1" if power > 0 => String::new(),
+                other => other.to_string(),
+            };
+            terms.push(match power {
+                0 => coefficient,
+                1 => format!("{}h", coefficient),
+                _ => format!("{}h^{}", coefficient, power),
+            });
+        }
+        if terms.is_empty() {
+            terms.push("0".to_string());
+        }
+
+        write!(f, "speed={}", terms.join("+"))?;
+        if let Some(max_speed) = &self.max_speed {
+            write!(f, ",max={}", max_speed)?;
+        }
+        if !self.penalty.is_zero() {
+            write!(f, ",penalty={}", self.penalty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Comma-separated `accel=2` or `speed=1+2h+h^3`, then optionally `max=10`
+/// and `penalty=3`.
+impl FromStr for BoatModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut model = BoatModel::default();
+        let mut curve = None;
+        for part in s.split(',') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or(format!("expected key=value, not {:?}", part))?;
+            let value = value.trim();
+            let speed = match key.trim() {
+                "accel" => vec![BigUint::zero(), parse_number(value)?],
+                "speed" => parse_polynomial(value)?,
+                "max" => {
+                    model.max_speed = Some(parse_number(value)?);
+                    continue;
+                }
+                "penalty" => {
+                    model.penalty = parse_number(value)?;
+                    continue;
+                }
+                other => return Err(format!("unknown boat setting {:?}", other)),
+            };
+            if curve.replace(speed).is_some() {
+                return Err("give either accel or speed, once".to_string());
+            }
+        }
+
+        if let Some(speed) = curve {
+            model.speed = BoatModel::with_speed(speed).speed;
+        }
+        Ok(model)
+    }
+}
+
+/// How many holds the ranges from `winning_holds` take in.
+pub fn count_holds(holds: &[Range<BigUint>]) -> BigUint {
+    holds.iter().map(|range| &range.end - &range.start).sum()
+}
+
+fn parse_number(s: &str) -> Result<BigUint, String> {
+    s.parse().map_err(|_| format!("invalid number {:?}", s))
+}
+
+/// Highest power of `h` a speed curve may use.
+const MAX_POWER: usize = 32;
+
+/// `1+2h+h^3`, terms in any order.
+fn parse_polynomial(s: &str) -> Result<Vec<BigUint>, String> {
+    let mut coefficients = Vec::new();
+    for term in s.split('+') {
+        let term = term.trim();
+        let (coefficient, power) = match term.split_once('h') {
+            None => (parse_number(term)?, 0),
+            Some((coefficient, power)) => {
+                let coefficient = match coefficient {
+                    "" => BigUint::one(),
+                    _ => parse_number(coefficient)?,
+                };
+                let power = match power.strip_prefix('^') {
+                    Some(power) => power
+                        .parse()
+                        .map_err(|_| format!("invalid power in {:?}", term))?,
+                    None if power.is_empty() => 1,
+                    None => return Err(format!("invalid term {:?}", term)),
+                };
+                (coefficient, power)
+            }
+        };
+        if power > MAX_POWER {
+            return Err(format!("powers above h^{} aren't supported", MAX_POWER));
+        }
+        if coefficients.len() <= power {
+            coefficients.resize(power + 1, BigUint::zero());
+        }
+        coefficients[power] += coefficient;
+    }
+    Ok(coefficients)
+}
+
+fn to_unsigned(value: BigInt) -> BigUint {
+    value.to_biguint().expect("holds are never negative")
+}
+
+/// Polynomials are coefficient lists, lowest power first.
+fn evaluate(polynomial: &[BigInt], x: &BigInt) -> BigInt {
+    polynomial
+        .iter()
+        .rev()
+        .fold(BigInt::zero(), |value, coefficient| value * x + coefficient)
+}
+
+fn sub(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let mut result = a.to_vec();
+    if result.len() < b.len() {
+        result.resize(b.len(), BigInt::zero());
+    }
+    for (term, coefficient) in result.iter_mut().zip(b) {
+        *term -= coefficient;
+    }
+    trim(result)
+}
+
+fn mul(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut result = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    trim(result)
+}
+
+fn trim(mut polynomial: Vec<BigInt>) -> Vec<BigInt> {
+    while polynomial.last().is_some_and(Zero::is_zero) {
+        polynomial.pop();
+    }
+    polynomial
+}
+
+/// `p(x + 1) - p(x)`, one degree lower than `p`.
+fn difference(polynomial: &[BigInt]) -> Vec<BigInt> {
+    // p(x + 1) expanded with binomial coefficients, row by row of Pascal's triangle
+    let mut shifted = vec![BigInt::zero(); polynomial.len()];
+    let mut row = vec![BigInt::one()];
+    for (power, coefficient) in polynomial.iter().enumerate() {
+        for (j, binomial) in row.iter().enumerate() {
+            shifted[j] += coefficient * binomial;
+        }
+        let mut next = vec![BigInt::one(); power + 2];
+        for j in 1..=power {
+            next[j] = &row[j - 1] + &row[j];
+        }
+        row = next;
+    }
+    sub(&shifted, polynomial)
+}
+
+/// The values in `start..end` where `polynomial` is positive, as sorted,
+/// non-overlapping ranges.
+fn positive_runs(polynomial: &[BigInt], start: &BigInt, end: &BigInt) -> Vec<Range<BigInt>> {
+    if start >= end {
+        return Vec::new();
+    }
+    if polynomial.len() <= 1 {
+        return match polynomial.first() {
+            Some(constant) if constant.is_positive() => vec![start.clone()..end.clone()],
+            _ => Vec::new(),
+        };
+    }
+
+    // Where the difference is positive the polynomial rises from each value to
+    // the next, elsewhere it doesn't, so between consecutive breaks it's monotone
+    let last = end - 1;
+    let mut breaks = vec![start.clone()];
+    for rising in positive_runs(&difference(polynomial), start, &last) {
+        breaks.extend([rising.start, rising.end]);
+    }
+    breaks.push(last);
+    breaks.dedup();
+
+    let mut runs = Vec::new();
+    for pair in breaks.windows(2) {
+        let (low, high) = (&pair[0], &pair[1]);
+        let positive = |x: &BigInt| evaluate(polynomial, x).is_positive();
+        match (positive(low), positive(high)) {
+            (true, true) => runs.push(low.clone()..high + 1),
+            (false, true) => {
+                let first = first_positive(polynomial, low, &(high + 1), true).unwrap();
+                runs.push(first..high + 1);
+            }
+            (true, false) => {
+                let first = first_positive(polynomial, low, &(high + 1), false).unwrap();
+                runs.push(low.clone()..first);
+            }
+            (false, false) => {}
+        }
+    }
+    // A lone value gives a window of its own
+    if breaks.len() == 1 && evaluate(polynomial, start).is_positive() {
+        runs.push(start.clone()..end.clone());
+    }
+    merge(runs)
+}
+
+/// The first value in `start..end` where whether `polynomial` is positive
+/// matches `want`, given that it's monotone there.
+fn first_positive(
+    polynomial: &[BigInt],
+    start: &BigInt,
+    end: &BigInt,
+    want: bool,
+) -> Option<BigInt> {
+    let (mut low, mut high) = (start.clone(), end.clone());
+    while low < high {
+        let middle: BigInt = (&low + &high) / 2;
+        if evaluate(polynomial, &middle).is_positive() == want {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    (low < *end).then_some(low)
+}
+
+/// The first value in `0..end` where `polynomial`, which never falls, reaches
+/// `target`.
+fn first_at_least(polynomial: &[BigInt], target: &BigInt, end: &BigInt) -> Option<BigInt> {
+    let below = sub(polynomial, &[target - 1]);
+    first_positive(&below, &BigInt::zero(), end, true)
+}
+
+fn merge(mut ranges: Vec<Range<BigInt>>) -> Vec<Range<BigInt>> {
+    ranges.sort_by(|a, b| a.start.cmp(&b.start));
+    let mut merged: Vec<Range<BigInt>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => {
+                if range.end > last.end {
+                    last.end = range.end;
+                }
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::ToPrimitive;
+
+    /// Tries every hold.
+    fn brute_force(model: &BoatModel, time: u64, distance: u64) -> Vec<u64> {
+        let time = BigUint::from(time);
+        let record = BigUint::from(distance);
+        (0..=time.to_u64().unwrap())
+            .filter(|&hold| model.travel_distance(&time, &hold.into()) > record)
+            .collect()
+    }
+
+    fn holds(model: &BoatModel, time: u64, distance: u64) -> Vec<u64> {
+        model
+            .winning_holds(&RaceRecord::new(time, distance))
+            .into_iter()
+            .flat_map(|range| range.start.to_u64().unwrap()..range.end.to_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_standard_boat() {
+        let model = BoatModel::default();
+        assert_eq!(model.to_string(), "speed=h");
+        assert_eq!(holds(&model, 7, 9), vec![2, 3, 4, 5]);
+        assert_eq!(
+            model.ways_to_win(&RaceRecord::new(71530u32, 940200)),
+            BigUint::from(71503u32)
+        );
+    }
+
+    #[test]
+    fn test_travel_distance() {
+        let model: BoatModel = "speed=2+h^2,max=20,penalty=1".parse().unwrap();
+        let time = BigUint::from(7u32);
+        let distances: Vec<u64> = (0..=7u32)
+            .map(|hold| model.travel_distance(&time, &hold.into()).to_u64().unwrap())
+            .collect();
+        // Speeds 2, 3, 6, 11, 18, 20, 20, 20 over 6, 5, 4, 3, 2, 1, 0, 0 milliseconds
+        assert_eq!(distances, vec![12, 15, 24, 33, 36, 20, 0, 0]);
+    }
+
+    #[test]
+    fn test_models_match_brute_force() {
+        let models = [
+            "accel=1",
+            "accel=3",
+            "accel=0",
+            "accel=2,penalty=3",
+            "accel=1,max=4",
+            "speed=5",
+            "speed=h^2",
+            "speed=1+2h+h^3,max=50,penalty=1",
+            "speed=10+h^3",
+            "speed=h^4,max=70",
+        ];
+        for description in models {
+            let model: BoatModel = description.parse().unwrap();
+            for time in 0..25u64 {
+                // The answer only changes as the record passes a hold's distance
+                let mut records = vec![0];
+                for hold in 0..=time {
+                    let reached = model.travel_distance(&time.into(), &hold.into());
+                    let reached = reached.to_u64().unwrap();
+                    records.extend([reached.saturating_sub(1), reached, reached + 1]);
+                }
+                for distance in records {
+                    assert_eq!(
+                        holds(&model, time, distance),
+                        brute_force(&model, time, distance),
+                        "{} over {} ms, record {}",
+                        description,
+                        time,
+                        distance
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_distance_curve_with_several_peaks() {
+        // Distances 100, 99, 144, 259, ..., 1059, 1044, 739, 0 rise, fall, rise
+        // and fall again, so holding for 1 ms loses between two winning runs
+        let model: BoatModel = "speed=10+h^3".parse().unwrap();
+        let ranges = model.winning_holds(&RaceRecord::new(10u32, 99));
+        assert_eq!(
+            ranges,
+            vec![
+                BigUint::from(0u32)..BigUint::from(1u32),
+                BigUint::from(2u32)..BigUint::from(10u32)
+            ]
+        );
+        assert_eq!(
+            model.ways_to_win(&RaceRecord::new(10u32, 99)),
+            BigUint::from(9u32)
+        );
+    }
+
+    #[test]
+    fn test_search_matches_closed_form() {
+        // A top speed the boat never reaches still sends it down the search
+        let record = RaceRecord::new(71530u32, 940200);
+        let closed: BoatModel = "accel=3,penalty=17".parse().unwrap();
+        let searched: BoatModel = "accel=3,penalty=17,max=1000000000".parse().unwrap();
+        assert_eq!(
+            closed.winning_holds(&record),
+            searched.winning_holds(&record)
+        );
+
+        let record = RaceRecord::new(
+            "40000000000000000000".parse::<BigUint>().unwrap(),
+            "1000000000000000000000000000000".parse().unwrap(),
+        );
+        let searched = BoatModel {
+            max_speed: Some(record.time.clone()),
+            ..BoatModel::default()
+        };
+        assert_eq!(
+            searched.winning_holds(&record),
+            vec![record.winning_holds()]
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        let model: BoatModel = "accel=2, max=10, penalty=3".parse().unwrap();
+        assert_eq!(
+            model,
+            BoatModel {
+                speed: vec![BigUint::zero(), BigUint::from(2u32)],
+                max_speed: Some(BigUint::from(10u32)),
+                penalty: BigUint::from(3u32),
+            }
+        );
+        assert_eq!(model.to_string(), "speed=2h,max=10,penalty=3");
+
+        let model: BoatModel = "speed=h^3+1+2h+h".parse().unwrap();
+        assert_eq!(model.to_string(), "speed=1+3h+h^3");
+        assert_eq!(model.to_string().parse(), Ok(model));
+        assert_eq!(
+            "speed=0h^2".parse::<BoatModel>().unwrap().to_string(),
+            "speed=0"
+        );
+
+        assert!("accel=2,speed=h".parse::<BoatModel>().is_err());
+        assert!("accel".parse::<BoatModel>().is_err());
+        assert!("drag=1".parse::<BoatModel>().is_err());
+        assert!("speed=2x".parse::<BoatModel>().is_err());
+        assert!("speed=h^-1".parse::<BoatModel>().is_err());
+        assert!("speed=h^1000000000000".parse::<BoatModel>().is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! Small helpers shared by the JSON and DOT exporters.
+
+/// A JSON string literal for `value`, quotes included. Also valid as a quoted
+/// Graphviz DOT id.
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(json_string("a\"b\\"), r#""a\"b\\""#);
+        assert_eq!(json_string("tab\there"), r#""tab\u0009here""#);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod cli;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod export;